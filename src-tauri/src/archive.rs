@@ -0,0 +1,378 @@
+use crate::exec::{exec_channel, shell_quote};
+use crate::sftp::{remote_join, temp_sibling};
+use crate::transfer::{finish, percent, register_task, transfer_speed, TransferEvent, TransferTasks};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+const ARCHIVE_BUFFER_SIZE: usize = 32 * 1024;
+const ARCHIVE_CANCELLED: &str = "传输已取消";
+/// 下载时总大小未知，按时间间隔上报进度
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+/// 远端 stderr 最多保留的字节数（用于错误信息）
+const STDERR_LIMIT: usize = 4096;
+
+#[derive(Clone, Copy, Deserialize)]
+pub enum ArchiveFormat {
+    #[serde(rename = "tar")]
+    Tar,
+    #[serde(rename = "tar.gz")]
+    TarGz,
+    #[serde(rename = "tar.xz")]
+    TarXz,
+    #[serde(rename = "zip")]
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// 根据文件名后缀识别格式
+    fn from_path(path: &str) -> Option<Self> {
+        let lower = path.to_ascii_lowercase();
+        if lower.ends_with(".tar.gz") || lower.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if lower.ends_with(".tar.xz") || lower.ends_with(".txz") {
+            Some(Self::TarXz)
+        } else if lower.ends_with(".tar") {
+            Some(Self::Tar)
+        } else if lower.ends_with(".zip") {
+            Some(Self::Zip)
+        } else {
+            None
+        }
+    }
+
+    fn tar_flag(&self) -> &'static str {
+        match self {
+            Self::TarGz => "z",
+            Self::TarXz => "J",
+            _ => "",
+        }
+    }
+
+    /// 命令的正常退出码：打包时 GNU tar 为 1 表示期间文件有变化，zip 为 18 表示部分文件无法读取，归档仍可用；
+    /// 解压命令包含 mkdir 和 tar/unzip，只有 0 表示成功
+    fn is_success(&self, exit_status: u32, packing: bool) -> bool {
+        match (self, packing) {
+            (_, false) => exit_status == 0,
+            (Self::Zip, true) => exit_status == 0 || exit_status == 18,
+            (_, true) => exit_status == 0 || exit_status == 1,
+        }
+    }
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase",
+    tag = "event",
+    content = "data"
+)]
+pub enum ArchiveEvent {
+    /// 上传时 val 为整体进度；下载时归档大小未知，val 恒为 0
+    Process {
+        val: u32,
+        transferred: u64,
+        speed: f64,
+    },
+    /// 归档已全部上传，等待远端解压完成
+    Extracting,
+    Finished {
+        transferred: u64,
+        elapsed_ms: u64,
+    },
+    Cancelled,
+}
+
+static ARCHIVE_TASKS: Lazy<TransferTasks> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+impl TransferEvent for ArchiveEvent {
+    fn finished(transferred: u64, elapsed_ms: u64) -> Self {
+        Self::Finished { transferred, elapsed_ms }
+    }
+
+    fn cancelled() -> Self {
+        Self::Cancelled
+    }
+}
+
+/// 在远端打包文件或目录，归档数据经 exec 通道直接流式写入本地文件，不占用远端磁盘
+#[tauri::command]
+pub async fn ssh_archive_download(
+    task_id: &str,
+    session_id: &str,
+    remote_path: &str,
+    local_path: &str,
+    format: ArchiveFormat,
+    on_event: tauri::ipc::Channel<ArchiveEvent>,
+) -> Result<String, String> {
+    let token = register_task(&ARCHIVE_TASKS, task_id).await;
+    let started = Instant::now();
+
+    let result = pack_to_local(session_id, remote_path, Path::new(local_path), format, &token, &on_event).await;
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(local_path).await;
+    }
+
+    ARCHIVE_TASKS.lock().await.remove(task_id);
+    finish(result, started, ARCHIVE_CANCELLED, &on_event)
+}
+
+/// 上传本地归档并在远端解压到 remote_dir（不存在时自动创建）
+/// tar 系列经 stdin 边传边解压；zip 无法从 stdin 解压，先写入远端临时文件，解压后删除
+/// format 为空时根据本地文件后缀识别
+#[tauri::command]
+pub async fn ssh_archive_extract_upload(
+    task_id: &str,
+    session_id: &str,
+    local_path: &str,
+    remote_dir: &str,
+    format: Option<ArchiveFormat>,
+    on_event: tauri::ipc::Channel<ArchiveEvent>,
+) -> Result<String, String> {
+    let format = format
+        .or_else(|| ArchiveFormat::from_path(local_path))
+        .ok_or_else(|| "Unsupported archive format".to_string())?;
+    let token = register_task(&ARCHIVE_TASKS, task_id).await;
+    let started = Instant::now();
+
+    let result = extract_from_local(session_id, Path::new(local_path), remote_dir, format, &token, &on_event).await;
+
+    ARCHIVE_TASKS.lock().await.remove(task_id);
+    finish(result, started, ARCHIVE_CANCELLED, &on_event)
+}
+
+#[tauri::command]
+pub async fn ssh_archive_cancel(task_id: String) -> Result<(), String> {
+    let mut map = ARCHIVE_TASKS.lock().await;
+
+    if let Some(token) = map.remove(&task_id) {
+        token.cancel();
+        Ok(())
+    } else {
+        Err("任务不存在".to_string())
+    }
+}
+
+/// 拆分为 (父目录, 名称)，打包时以父目录为工作目录，归档内保留顶层目录名
+fn split_parent(path: &str) -> (String, String) {
+    let trimmed = path.trim_end_matches('/');
+    match trimmed.rfind('/') {
+        Some(0) => ("/".to_string(), trimmed[1..].to_string()),
+        Some(pos) => (trimmed[..pos].to_string(), trimmed[pos + 1..].to_string()),
+        None => (".".to_string(), trimmed.to_string()),
+    }
+}
+
+async fn pack_to_local(
+    session_id: &str,
+    remote_path: &str,
+    local_path: &Path,
+    format: ArchiveFormat,
+    token: &CancellationToken,
+    on_event: &tauri::ipc::Channel<ArchiveEvent>,
+) -> Result<u64, String> {
+    let (parent, name) = split_parent(remote_path);
+    if name.is_empty() {
+        return Err("Cannot archive the root directory".to_string());
+    }
+    let command = match format {
+        ArchiveFormat::Zip => format!(
+            "cd {} && zip -qr - {}",
+            shell_quote(&parent),
+            shell_quote(&name)
+        ),
+        _ => format!(
+            "tar -C {} -c{}f - -- {}",
+            shell_quote(&parent),
+            format.tar_flag(),
+            shell_quote(&name)
+        ),
+    };
+
+    let mut channel = exec_channel(session_id, &command).await?;
+    let mut local_file = tokio::fs::File::create(local_path)
+        .await
+        .map_err(|e| e.to_string())?;
+    let started = Instant::now();
+    let mut last_report = Instant::now();
+    let mut transferred: u64 = 0;
+    let mut stderr: Vec<u8> = Vec::new();
+    let mut exit_status: Option<u32> = None;
+
+    loop {
+        let msg = tokio::select! {
+            _ = token.cancelled() => {
+                let _ = channel.close().await;
+                return Err(ARCHIVE_CANCELLED.to_string());
+            }
+            msg = channel.wait() => msg,
+        };
+        match msg {
+            Some(russh::ChannelMsg::Data { ref data }) => {
+                local_file.write_all(data).await.map_err(|e| e.to_string())?;
+                transferred += data.len() as u64;
+                if last_report.elapsed() >= PROGRESS_INTERVAL {
+                    last_report = Instant::now();
+                    let _ = on_event.send(ArchiveEvent::Process {
+                        val: 0,
+                        transferred,
+                        speed: transfer_speed(transferred, started),
+                    });
+                }
+            }
+            Some(russh::ChannelMsg::ExtendedData { ref data, ext: 1 }) => {
+                append_stderr(&mut stderr, data);
+            }
+            Some(russh::ChannelMsg::ExitStatus { exit_status: status }) => {
+                exit_status = Some(status);
+            }
+            Some(russh::ChannelMsg::Close) | None => break,
+            _ => {}
+        }
+    }
+    local_file.flush().await.map_err(|e| e.to_string())?;
+
+    check_exit(format, exit_status, &stderr, true)?;
+    Ok(transferred)
+}
+
+async fn extract_from_local(
+    session_id: &str,
+    local_path: &Path,
+    remote_dir: &str,
+    format: ArchiveFormat,
+    token: &CancellationToken,
+    on_event: &tauri::ipc::Channel<ArchiveEvent>,
+) -> Result<u64, String> {
+    let mut local_file = tokio::fs::File::open(local_path)
+        .await
+        .map_err(|e| e.to_string())?;
+    let total = local_file
+        .metadata()
+        .await
+        .map_err(|e| e.to_string())?
+        .len();
+
+    let dir = shell_quote(remote_dir);
+    let command = match format {
+        ArchiveFormat::Zip => {
+            let tmp = shell_quote(&temp_sibling(&remote_join(remote_dir, "archive.zip"))?);
+            format!(
+                "mkdir -p {dir} && cat > {tmp} && unzip -oq {tmp} -d {dir}; status=$?; rm -f {tmp}; exit $status"
+            )
+        }
+        _ => format!("mkdir -p {dir} && tar -C {dir} -x{}f -", format.tar_flag()),
+    };
+
+    let mut channel = exec_channel(session_id, &command).await?;
+
+    // 写入单独放到任务中，等待窗口期间仍需及时消费通道消息
+    let mut writer = channel.make_writer();
+    let progress = on_event.clone();
+    let mut upload = tokio::spawn(async move {
+        let started = Instant::now();
+        let mut buffer = vec![0u8; ARCHIVE_BUFFER_SIZE];
+        let mut transferred: u64 = 0;
+        let mut last_progress = u32::MAX;
+        loop {
+            let n = local_file.read(&mut buffer).await.map_err(|e| e.to_string())?;
+            if n == 0 {
+                break;
+            }
+            writer.write_all(&buffer[..n]).await.map_err(|e| e.to_string())?;
+            transferred += n as u64;
+            let val = percent(transferred, total);
+            if val != last_progress {
+                last_progress = val;
+                let _ = progress.send(ArchiveEvent::Process {
+                    val,
+                    transferred,
+                    speed: transfer_speed(transferred, started),
+                });
+            }
+        }
+        // 关闭写端即发送 EOF，远端 tar/cat 随之结束读取
+        writer.shutdown().await.map_err(|e| e.to_string())?;
+        Ok::<u64, String>(transferred)
+    });
+
+    let mut transferred: Option<u64> = None;
+    let mut stderr: Vec<u8> = Vec::new();
+    let mut exit_status: Option<u32> = None;
+
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => {
+                upload.abort();
+                let _ = channel.close().await;
+                return Err(ARCHIVE_CANCELLED.to_string());
+            }
+            result = &mut upload, if transferred.is_none() => {
+                match result {
+                    Ok(Ok(n)) => {
+                        transferred = Some(n);
+                        let _ = on_event.send(ArchiveEvent::Extracting);
+                    }
+                    Ok(Err(e)) => {
+                        let _ = channel.close().await;
+                        return Err(e);
+                    }
+                    Err(e) => return Err(e.to_string()),
+                }
+            }
+            msg = channel.wait() => match msg {
+                Some(russh::ChannelMsg::ExtendedData { ref data, ext: 1 }) => {
+                    append_stderr(&mut stderr, data);
+                }
+                Some(russh::ChannelMsg::ExitStatus { exit_status: status }) => {
+                    exit_status = Some(status);
+                }
+                Some(russh::ChannelMsg::Close) | None => break,
+                _ => {}
+            },
+        }
+    }
+
+    check_exit(format, exit_status, &stderr, false)?;
+    match transferred {
+        Some(n) => Ok(n),
+        // 远端提前退出（如归档损坏），上传未完成
+        None => {
+            upload.abort();
+            Err("Remote extraction ended before upload completed".to_string())
+        }
+    }
+}
+
+fn append_stderr(stderr: &mut Vec<u8>, data: &[u8]) {
+    let room = STDERR_LIMIT.saturating_sub(stderr.len());
+    stderr.extend_from_slice(&data[..data.len().min(room)]);
+}
+
+fn check_exit(format: ArchiveFormat, exit_status: Option<u32>, stderr: &[u8], packing: bool) -> Result<(), String> {
+    match exit_status {
+        Some(status) if format.is_success(status, packing) => Ok(()),
+        Some(127) => Err(format!(
+            "{} is not installed on the remote host",
+            match format {
+                ArchiveFormat::Zip => "zip/unzip",
+                _ => "tar",
+            }
+        )),
+        Some(status) => {
+            let message = String::from_utf8_lossy(stderr).trim().to_string();
+            if message.is_empty() {
+                Err(format!("Remote command exited with status {}", status))
+            } else {
+                Err(message)
+            }
+        }
+        None => Err("Remote command terminated without exit status".to_string()),
+    }
+}
+
@@ -0,0 +1,366 @@
+use crate::exec::{exec_channel, exec_output, shell_quote};
+use crate::sftp::put_ssh_string;
+use crate::ssh::{ssh_get_raw_sftp, ssh_get_sftp, SFTP_UNAVAILABLE};
+use data_encoding::HEXLOWER;
+use log::info;
+use ring::digest::Algorithm;
+use russh_sftp::client::SftpSession;
+use russh_sftp::protocol::{FileAttributes, OpenFlags, Packet};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// SFTP 校验扩展（draft-ietf-secsh-filexfer-extensions），ProFTPD 等服务端支持
+const CHECK_FILE: &str = "check-file";
+const CHECK_FILE_NAME: &str = "check-file-name";
+
+const HASH_BUFFER_SIZE: usize = 64 * 1024;
+
+/// 支持的算法
+#[derive(Clone, Copy, PartialEq)]
+enum HashAlgorithm {
+    Sha256,
+    Sha1,
+    Md5,
+}
+
+impl HashAlgorithm {
+    fn parse(name: Option<&str>) -> Result<Self, String> {
+        match name.map(|n| n.to_ascii_lowercase()).as_deref() {
+            None | Some("sha256") => Ok(Self::Sha256),
+            Some("sha1") => Ok(Self::Sha1),
+            Some("md5") => Ok(Self::Md5),
+            Some(other) => Err(format!("Unsupported hash algorithm: {}", other)),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Sha1 => "sha1",
+            Self::Md5 => "md5",
+        }
+    }
+
+    fn hex_len(&self) -> usize {
+        match self {
+            Self::Sha256 => 64,
+            Self::Sha1 => 40,
+            Self::Md5 => 32,
+        }
+    }
+
+    /// 远端计算命令，优先 coreutils，其次 BSD/macOS 自带工具
+    fn command(&self, path: &str) -> String {
+        let path = shell_quote(path);
+        match self {
+            Self::Sha256 => format!("sha256sum -- {path} 2>/dev/null || shasum -a 256 -- {path}"),
+            Self::Sha1 => format!("sha1sum -- {path} 2>/dev/null || shasum -a 1 -- {path}"),
+            Self::Md5 => format!("md5sum -- {path} 2>/dev/null || md5 -q -- {path}"),
+        }
+    }
+
+    /// 本地计算使用的摘要算法，ring 不支持 md5
+    fn digest(&self) -> Option<&'static Algorithm> {
+        match self {
+            Self::Sha256 => Some(&ring::digest::SHA256),
+            Self::Sha1 => Some(&ring::digest::SHA1_FOR_LEGACY_USE_ONLY),
+            Self::Md5 => None,
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteChecksum {
+    pub algorithm: String,
+    /// 十六进制小写
+    pub hash: String,
+    /// 计算方式: "check-file"（SFTP 扩展）、"exec"（远端命令）或 "sftp"（下载后本地计算）
+    pub method: String,
+    pub size: u64,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChecksumCompare {
+    pub equal: bool,
+    pub algorithm: String,
+    /// 大小不同时不再计算两端的校验值
+    pub local_hash: Option<String>,
+    pub remote_hash: Option<String>,
+    pub method: Option<String>,
+    pub local_size: u64,
+    pub remote_size: u64,
+}
+
+/// 计算远端文件校验值，algorithm 为 "sha256"（默认）、"sha1" 或 "md5"
+#[tauri::command]
+pub async fn ssh_sftp_checksum(
+    session_id: &str,
+    file_path: &str,
+    algorithm: Option<String>,
+) -> Result<RemoteChecksum, String> {
+    let algorithm = HashAlgorithm::parse(algorithm.as_deref())?;
+    let sftp = optional_sftp(session_id).await?;
+    remote_checksum(session_id, sftp.as_deref(), file_path, algorithm).await
+}
+
+/// 比较本地文件与远端文件内容是否一致（用于校验上传结果）
+#[tauri::command]
+pub async fn ssh_checksum_compare(
+    session_id: &str,
+    local_path: &str,
+    remote_path: &str,
+    algorithm: Option<String>,
+) -> Result<ChecksumCompare, String> {
+    let algorithm = HashAlgorithm::parse(algorithm.as_deref())?;
+    let digest = algorithm
+        .digest()
+        .ok_or_else(|| format!("{} is not supported for local files", algorithm.name()))?;
+
+    // 先比较大小，不同时无需读取整个本地文件
+    let local_size = tokio::fs::metadata(local_path)
+        .await
+        .map_err(|e| e.to_string())?
+        .len();
+    let sftp = optional_sftp(session_id).await?;
+    let remote_size = remote_size(session_id, sftp.as_deref(), remote_path).await?;
+    if remote_size != local_size {
+        return Ok(ChecksumCompare {
+            equal: false,
+            algorithm: algorithm.name().to_string(),
+            local_hash: None,
+            remote_hash: None,
+            method: None,
+            local_size,
+            remote_size,
+        });
+    }
+
+    let (local_hash, local_size) = local_file_hash(Path::new(local_path), digest).await?;
+    let remote = remote_checksum(session_id, sftp.as_deref(), remote_path, algorithm).await?;
+    Ok(ChecksumCompare {
+        equal: remote.hash == local_hash && remote.size == local_size,
+        algorithm: algorithm.name().to_string(),
+        local_hash: Some(local_hash),
+        remote_hash: Some(remote.hash),
+        method: Some(remote.method),
+        local_size,
+        remote_size,
+    })
+}
+
+/// 服务端不提供 sftp 子系统（仅支持 SCP）时返回 None
+async fn optional_sftp(session_id: &str) -> Result<Option<Arc<SftpSession>>, String> {
+    match ssh_get_sftp(session_id).await {
+        Ok(sftp) => Ok(Some(sftp)),
+        Err(err) if err == SFTP_UNAVAILABLE => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// 远端文件大小，没有 sftp 时通过 `wc -c` 获取
+async fn remote_size(
+    session_id: &str,
+    sftp: Option<&SftpSession>,
+    path: &str,
+) -> Result<u64, String> {
+    if let Some(sftp) = sftp {
+        let metadata = sftp.metadata(path).await.map_err(|e| e.to_string())?;
+        return Ok(metadata.size.unwrap_or(0));
+    }
+    let output = exec_output(session_id, &format!("wc -c < {}", shell_quote(path))).await?;
+    if !output.success() {
+        return Err(output.error_message());
+    }
+    let text = output.stdout_text();
+    text.trim()
+        .parse()
+        .map_err(|_| format!("Invalid size output: {}", text.trim()))
+}
+
+/// 依次尝试 check-file 扩展 → 远端命令 → sftp 下载后本地计算；没有 sftp 时只使用远端命令
+async fn remote_checksum(
+    session_id: &str,
+    sftp: Option<&SftpSession>,
+    file_path: &str,
+    algorithm: HashAlgorithm,
+) -> Result<RemoteChecksum, String> {
+    let size = remote_size(session_id, sftp, file_path).await?;
+
+    let result = |hash: String, method: &str| RemoteChecksum {
+        algorithm: algorithm.name().to_string(),
+        hash,
+        method: method.to_string(),
+        size,
+    };
+
+    if sftp.is_some() {
+        match check_file_extension(session_id, file_path, algorithm).await {
+            Ok(Some(hash)) => return Ok(result(hash, "check-file")),
+            Ok(None) => {}
+            Err(e) => info!("check-file failed, fall back to exec: {}", e),
+        }
+    }
+
+    match exec_checksum(session_id, file_path, algorithm).await {
+        Ok(Some(hash)) => return Ok(result(hash, "exec")),
+        Ok(None) => {}
+        Err(e) => info!("checksum command failed, fall back to sftp: {}", e),
+    }
+
+    let sftp =
+        sftp.ok_or_else(|| format!("{} is not available on the remote host", algorithm.name()))?;
+    let digest = algorithm
+        .digest()
+        .ok_or_else(|| format!("{} is not available on the remote host", algorithm.name()))?;
+    Ok(result(
+        remote_file_hash(sftp, file_path, digest).await?,
+        "sftp",
+    ))
+}
+
+/// 流式计算本地文件的摘要（十六进制小写），返回摘要和读取的字节数
+pub async fn local_file_hash(
+    path: &Path,
+    digest: &'static Algorithm,
+) -> Result<(String, u64), String> {
+    let file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| e.to_string())?;
+    stream_hash(file, digest).await
+}
+
+/// 通过 sftp 读取远端文件并计算摘要（十六进制小写）
+pub async fn remote_file_hash(
+    sftp: &SftpSession,
+    path: &str,
+    digest: &'static Algorithm,
+) -> Result<String, String> {
+    let file = sftp.open(path).await.map_err(|e| e.to_string())?;
+    stream_hash(file, digest).await.map(|(hash, _)| hash)
+}
+
+async fn stream_hash(
+    mut reader: impl AsyncRead + Unpin,
+    digest: &'static Algorithm,
+) -> Result<(String, u64), String> {
+    let mut ctx = ring::digest::Context::new(digest);
+    let mut buffer = vec![0u8; HASH_BUFFER_SIZE];
+    let mut size: u64 = 0;
+    loop {
+        let n = reader.read(&mut buffer).await.map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        size += n as u64;
+        ctx.update(&buffer[..n]);
+    }
+    Ok((HEXLOWER.encode(ctx.finish().as_ref()), size))
+}
+
+/// 服务端不支持该扩展时返回 None
+/// check-file-name 按路径计算；只支持 check-file 的服务端需要先打开文件，按句柄计算
+async fn check_file_extension(
+    session_id: &str,
+    file_path: &str,
+    algorithm: HashAlgorithm,
+) -> Result<Option<String>, String> {
+    let raw = ssh_get_raw_sftp(session_id).await?;
+    let reply = if raw.extensions.contains_key(CHECK_FILE_NAME) {
+        raw.session
+            .extended(CHECK_FILE_NAME, check_file_request(file_path, algorithm))
+            .await
+    } else if raw.extensions.contains_key(CHECK_FILE) {
+        let handle = raw
+            .session
+            .open(file_path, OpenFlags::READ, FileAttributes::empty())
+            .await
+            .map_err(|e| e.to_string())?
+            .handle;
+        let reply = raw
+            .session
+            .extended(CHECK_FILE, check_file_request(&handle, algorithm))
+            .await;
+        let _ = raw.session.close(handle).await;
+        reply
+    } else {
+        return Ok(None);
+    };
+    let reply = match reply {
+        Ok(Packet::ExtendedReply(reply)) => reply.data,
+        Ok(Packet::Status(status)) => return Err(status.error_message),
+        Ok(_) => return Err("Unexpected reply to check-file".to_string()),
+        Err(e) => return Err(e.to_string()),
+    };
+
+    // string hash-algo-used, byte[] hash
+    if reply.len() < 4 {
+        return Err("Malformed check-file reply".to_string());
+    }
+    let name_len = u32::from_be_bytes([reply[0], reply[1], reply[2], reply[3]]) as usize;
+    let Some(used) = reply.get(4..4 + name_len) else {
+        return Err("Malformed check-file reply".to_string());
+    };
+    if !used.eq_ignore_ascii_case(algorithm.name().as_bytes()) {
+        return Ok(None);
+    }
+    let hash = HEXLOWER.encode(&reply[4 + name_len..]);
+    if hash.len() != algorithm.hex_len() {
+        return Err("Malformed check-file reply".to_string());
+    }
+    Ok(Some(hash))
+}
+
+/// string name/handle, string hash-algorithm-list, uint64 start-offset, uint64 length, uint32 block-size
+/// length 和 block-size 为 0 表示整个文件计算一个校验值
+fn check_file_request(target: &str, algorithm: HashAlgorithm) -> Vec<u8> {
+    let mut data = Vec::new();
+    put_ssh_string(&mut data, target.as_bytes());
+    put_ssh_string(&mut data, algorithm.name().as_bytes());
+    data.extend_from_slice(&0u64.to_be_bytes());
+    data.extend_from_slice(&0u64.to_be_bytes());
+    data.extend_from_slice(&0u32.to_be_bytes());
+    data
+}
+
+/// 命令不存在或输出无法解析时返回 None
+async fn exec_checksum(
+    session_id: &str,
+    file_path: &str,
+    algorithm: HashAlgorithm,
+) -> Result<Option<String>, String> {
+    let mut channel = exec_channel(session_id, &algorithm.command(file_path)).await?;
+    let mut stdout: Vec<u8> = Vec::new();
+    let mut exit_status: Option<u32> = None;
+    loop {
+        match channel.wait().await {
+            Some(russh::ChannelMsg::Data { ref data }) => stdout.extend_from_slice(data),
+            Some(russh::ChannelMsg::ExitStatus {
+                exit_status: status,
+            }) => {
+                exit_status = Some(status);
+            }
+            Some(russh::ChannelMsg::Close) | None => break,
+            _ => {}
+        }
+    }
+    if exit_status != Some(0) {
+        return Ok(None);
+    }
+
+    // 输出格式: `<hash>  <path>` 或 md5 -q 的 `<hash>`
+    let output = String::from_utf8_lossy(&stdout);
+    let hash = output
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .trim_start_matches('\\')
+        .to_ascii_lowercase();
+    if hash.len() == algorithm.hex_len() && hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(Some(hash))
+    } else {
+        Ok(None)
+    }
+}
@@ -0,0 +1,430 @@
+use crate::exec::{exec_channel, shell_quote};
+use crate::sftp::{put_ssh_string, remote_join};
+use crate::ssh::{ssh_get_raw_sftp, ssh_get_sftp, RawSftp};
+use crate::transfer::{percent, register_task, TransferTasks};
+use once_cell::sync::Lazy;
+use russh_sftp::client::SftpSession;
+use russh_sftp::protocol::{FileAttributes, OpenFlags, Packet, StatusCode};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+/// OpenSSH 9.0+ 支持的服务端数据复制扩展
+const COPY_DATA: &str = "copy-data";
+/// 单次 copy-data 请求复制的字节数，决定进度粒度
+const COPY_CHUNK_SIZE: u64 = 16 * 1024 * 1024;
+/// cp 方式下轮询目标大小的间隔；目录需要在远端执行 du，间隔更长
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+const DIR_POLL_INTERVAL: Duration = Duration::from_secs(5);
+const COPY_CANCELLED: &str = "复制已取消";
+
+#[derive(Clone, serde::Serialize)]
+#[serde(
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase",
+    tag = "event",
+    content = "data"
+)]
+pub enum RemoteCopyEvent {
+    /// 开始复制某个文件（仅 copy-data 方式）
+    File {
+        path: String,
+        size: u64,
+    },
+    /// cp 方式下 copied 为目标的磁盘占用，仅作估算
+    Process {
+        val: u32,
+        copied: u64,
+        total: u64,
+    },
+    Finished {
+        copied: u64,
+        elapsed_ms: u64,
+        /// 实际使用的方式: "copy-data" 或 "cp"
+        method: String,
+    },
+    Cancelled,
+}
+
+static COPY_TASKS: Lazy<TransferTasks> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+enum EntryKind {
+    Dir,
+    File,
+    Symlink,
+}
+
+/// 待复制的条目（先序，目录先于其子项）
+struct CopyEntry {
+    src: String,
+    dst: String,
+    kind: EntryKind,
+    size: u64,
+    permissions: Option<u32>,
+    mtime: Option<u32>,
+    atime: Option<u32>,
+}
+
+/// 在服务端复制文件或目录，数据不经过本地
+/// dst 为已存在的目录时复制到其下（与 cp 一致）；复制目录需指定 recursive
+#[tauri::command]
+pub async fn ssh_sftp_copy(
+    task_id: &str,
+    session_id: &str,
+    src: &str,
+    dst: &str,
+    recursive: bool,
+    on_event: tauri::ipc::Channel<RemoteCopyEvent>,
+) -> Result<String, String> {
+    let sftp = ssh_get_sftp(session_id).await?;
+    let src = if src.len() > 1 { src.trim_end_matches('/') } else { src };
+    let src_meta = sftp.symlink_metadata(src).await.map_err(|e| e.to_string())?;
+    let is_dir = src_meta.file_type().is_dir();
+    if is_dir && !recursive {
+        return Err("Source is a directory, recursive copy is required".to_string());
+    }
+
+    let mut dst = dst.trim_end_matches('/').to_string();
+    if matches!(sftp.metadata(dst.as_str()).await, Ok(meta) if meta.file_type().is_dir()) {
+        let name = src.rsplit('/').next().unwrap_or(src);
+        dst = remote_join(&dst, name);
+    }
+    if dst == src || dst.starts_with(&format!("{}/", src)) {
+        return Err("Cannot copy a directory into itself".to_string());
+    }
+
+    let token = register_task(&COPY_TASKS, task_id).await;
+    let started = Instant::now();
+
+    let result = async {
+        let raw = ssh_get_raw_sftp(session_id).await.ok();
+        match raw {
+            Some(raw) if raw.extensions.contains_key(COPY_DATA) => {
+                let entries = collect_entries(&sftp, src, &dst, &token).await?;
+                let total: u64 = entries.iter().map(|e| e.size).sum();
+                copy_with_extension(&sftp, &raw, &entries, total, &token, &on_event)
+                    .await
+                    .map(|copied| (copied, COPY_DATA))
+            }
+            _ => {
+                // cp 方式只用于估算进度，目录总大小用一次 du 获取，无需遍历
+                let total = if is_dir {
+                    disk_usage(session_id, src).await
+                } else {
+                    src_meta.size.unwrap_or(0)
+                };
+                copy_with_cp(session_id, &sftp, src, &dst, is_dir, total, &token, &on_event)
+                    .await
+                    .map(|copied| (copied, "cp"))
+            }
+        }
+    }
+    .await;
+
+    COPY_TASKS.lock().await.remove(task_id);
+    match result {
+        Ok((copied, method)) => {
+            let _ = on_event.send(RemoteCopyEvent::Finished {
+                copied,
+                elapsed_ms: started.elapsed().as_millis() as u64,
+                method: method.to_string(),
+            });
+            Ok(dst)
+        }
+        Err(e) => {
+            if e == COPY_CANCELLED {
+                let _ = on_event.send(RemoteCopyEvent::Cancelled);
+            }
+            Err(e)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn ssh_sftp_copy_cancel(task_id: String) -> Result<(), String> {
+    let mut map = COPY_TASKS.lock().await;
+
+    if let Some(token) = map.remove(&task_id) {
+        token.cancel();
+        Ok(())
+    } else {
+        Err("任务不存在".to_string())
+    }
+}
+
+/// 遍历源路径，生成先序的复制清单
+async fn collect_entries(
+    sftp: &SftpSession,
+    src: &str,
+    dst: &str,
+    token: &CancellationToken,
+) -> Result<Vec<CopyEntry>, String> {
+    let mut entries = Vec::new();
+    let meta = sftp.symlink_metadata(src).await.map_err(|e| e.to_string())?;
+    let mut stack = vec![(src.to_string(), dst.to_string(), meta)];
+
+    while let Some((src, dst, meta)) = stack.pop() {
+        if token.is_cancelled() {
+            return Err(COPY_CANCELLED.to_string());
+        }
+        let file_type = meta.file_type();
+        let kind = if file_type.is_symlink() {
+            EntryKind::Symlink
+        } else if file_type.is_dir() {
+            EntryKind::Dir
+        } else {
+            EntryKind::File
+        };
+        if matches!(kind, EntryKind::Dir) {
+            let children = sftp.read_dir(src.as_str()).await.map_err(|e| e.to_string())?;
+            for child in children {
+                let name = child.file_name();
+                stack.push((remote_join(&src, &name), remote_join(&dst, &name), child.metadata()));
+            }
+        }
+        entries.push(CopyEntry {
+            size: if matches!(kind, EntryKind::File) { meta.size.unwrap_or(0) } else { 0 },
+            permissions: meta.permissions,
+            mtime: meta.mtime,
+            atime: meta.atime,
+            src,
+            dst,
+            kind,
+        });
+    }
+    Ok(entries)
+}
+
+async fn copy_with_extension(
+    sftp: &SftpSession,
+    raw: &Arc<RawSftp>,
+    entries: &[CopyEntry],
+    total: u64,
+    token: &CancellationToken,
+    on_event: &tauri::ipc::Channel<RemoteCopyEvent>,
+) -> Result<u64, String> {
+    let mut copied: u64 = 0;
+    let mut last_progress = u32::MAX;
+
+    for entry in entries {
+        if token.is_cancelled() {
+            return Err(COPY_CANCELLED.to_string());
+        }
+        match entry.kind {
+            EntryKind::Dir => {
+                if sftp.try_exists(entry.dst.as_str()).await.unwrap_or(false) {
+                    continue;
+                }
+                sftp.create_dir(entry.dst.as_str())
+                    .await
+                    .map_err(|e| format!("{}: {}", entry.dst, e))?;
+            }
+            EntryKind::Symlink => {
+                let target = sftp
+                    .read_link(entry.src.as_str())
+                    .await
+                    .map_err(|e| format!("{}: {}", entry.src, e))?;
+                sftp.symlink(entry.dst.as_str(), target)
+                    .await
+                    .map_err(|e| format!("{}: {}", entry.dst, e))?;
+            }
+            EntryKind::File => {
+                let _ = on_event.send(RemoteCopyEvent::File {
+                    path: entry.src.clone(),
+                    size: entry.size,
+                });
+                copy_file_data(raw, entry, token, &mut |n| {
+                    copied += n;
+                    let val = percent(copied, total);
+                    if val != last_progress {
+                        last_progress = val;
+                        let _ = on_event.send(RemoteCopyEvent::Process { val, copied, total });
+                    }
+                })
+                .await?;
+                // 保留修改时间
+                let mut attrs = FileAttributes::empty();
+                attrs.mtime = entry.mtime;
+                attrs.atime = entry.atime.or(entry.mtime);
+                let _ = sftp.set_metadata(entry.dst.as_str(), attrs).await;
+            }
+        }
+    }
+
+    // 目录属性最后由内向外设置，避免只读目录导致子项无法写入、子项写入改变目录 mtime
+    for entry in entries.iter().rev().filter(|e| matches!(e.kind, EntryKind::Dir)) {
+        let mut attrs = FileAttributes::empty();
+        attrs.permissions = entry.permissions;
+        attrs.mtime = entry.mtime;
+        attrs.atime = entry.atime.or(entry.mtime);
+        let _ = sftp.set_metadata(entry.dst.as_str(), attrs).await;
+    }
+    Ok(copied)
+}
+
+/// 通过 copy-data 扩展复制单个文件，on_copied 参数为本次复制的字节数
+async fn copy_file_data(
+    raw: &Arc<RawSftp>,
+    entry: &CopyEntry,
+    token: &CancellationToken,
+    on_copied: &mut (dyn FnMut(u64) + Send),
+) -> Result<(), String> {
+    let read_handle = raw
+        .session
+        .open(entry.src.as_str(), OpenFlags::READ, FileAttributes::empty())
+        .await
+        .map_err(|e| format!("{}: {}", entry.src, e))?
+        .handle;
+    let mut attrs = FileAttributes::empty();
+    attrs.permissions = entry.permissions;
+    let write_handle = match raw
+        .session
+        .open(
+            entry.dst.as_str(),
+            OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNCATE,
+            attrs,
+        )
+        .await
+    {
+        Ok(handle) => handle.handle,
+        Err(e) => {
+            let _ = raw.session.close(read_handle).await;
+            return Err(format!("{}: {}", entry.dst, e));
+        }
+    };
+
+    let result = async {
+        let mut offset: u64 = 0;
+        while offset < entry.size {
+            if token.is_cancelled() {
+                return Err(COPY_CANCELLED.to_string());
+            }
+            let length = COPY_CHUNK_SIZE.min(entry.size - offset);
+            // string read-handle, uint64 read-offset, uint64 length, string write-handle, uint64 write-offset
+            let mut data = Vec::with_capacity(read_handle.len() + write_handle.len() + 32);
+            put_ssh_string(&mut data, read_handle.as_bytes());
+            data.extend_from_slice(&offset.to_be_bytes());
+            data.extend_from_slice(&length.to_be_bytes());
+            put_ssh_string(&mut data, write_handle.as_bytes());
+            data.extend_from_slice(&offset.to_be_bytes());
+
+            match raw.session.extended(COPY_DATA, data).await {
+                Ok(Packet::Status(status)) if status.status_code == StatusCode::Ok => {}
+                Ok(Packet::Status(status)) => return Err(format!("{}: {}", entry.src, status.error_message)),
+                Ok(_) => return Err("Unexpected reply to copy-data".to_string()),
+                Err(e) => return Err(e.to_string()),
+            }
+            offset += length;
+            on_copied(length);
+        }
+        Ok(())
+    }
+    .await;
+
+    let _ = raw.session.close(read_handle).await;
+    let _ = raw.session.close(write_handle).await;
+    result
+}
+
+/// 回退：`cp -a` 复制，期间轮询目标大小估算进度
+#[allow(clippy::too_many_arguments)]
+async fn copy_with_cp(
+    session_id: &str,
+    sftp: &SftpSession,
+    src: &str,
+    dst: &str,
+    is_dir: bool,
+    total: u64,
+    token: &CancellationToken,
+    on_event: &tauri::ipc::Channel<RemoteCopyEvent>,
+) -> Result<u64, String> {
+    let command = format!("cp -a -- {} {}", shell_quote(src), shell_quote(dst));
+    let mut channel = exec_channel(session_id, &command).await?;
+    let mut stderr: Vec<u8> = Vec::new();
+    let mut exit_status: Option<u32> = None;
+    let mut last_progress = u32::MAX;
+    let mut ticker = tokio::time::interval(if is_dir { DIR_POLL_INTERVAL } else { POLL_INTERVAL });
+    // du 耗时可能超过轮询间隔，错过的轮询不再补发，避免 du 连续执行
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => {
+                let _ = channel.signal(russh::Sig::TERM).await;
+                let _ = channel.close().await;
+                return Err(COPY_CANCELLED.to_string());
+            }
+            // 总大小未知（du 失败）时不估算进度
+            _ = ticker.tick(), if total > 0 => {
+                let copied = copied_size(session_id, sftp, dst, is_dir).await.min(total);
+                let val = percent(copied, total);
+                if val != last_progress {
+                    last_progress = val;
+                    let _ = on_event.send(RemoteCopyEvent::Process { val, copied, total });
+                }
+            }
+            msg = channel.wait() => match msg {
+                Some(russh::ChannelMsg::ExtendedData { ref data, ext: 1 }) => {
+                    stderr.extend_from_slice(data);
+                }
+                Some(russh::ChannelMsg::ExitStatus { exit_status: status }) => {
+                    exit_status = Some(status);
+                }
+                Some(russh::ChannelMsg::Close) | None => break,
+                _ => {}
+            },
+        }
+    }
+
+    match exit_status {
+        Some(0) => {
+            let _ = on_event.send(RemoteCopyEvent::Process { val: 100, copied: total, total });
+            Ok(total)
+        }
+        Some(status) => {
+            let message = String::from_utf8_lossy(&stderr).trim().to_string();
+            if message.is_empty() {
+                Err(format!("cp exited with status {}", status))
+            } else {
+                Err(message)
+            }
+        }
+        None => Err("cp terminated without exit status".to_string()),
+    }
+}
+
+/// 目标当前大小：文件直接取 stat，目录使用 `du -sk`
+async fn copied_size(session_id: &str, sftp: &SftpSession, dst: &str, is_dir: bool) -> u64 {
+    if !is_dir {
+        return sftp
+            .metadata(dst)
+            .await
+            .ok()
+            .and_then(|meta| meta.size)
+            .unwrap_or(0);
+    }
+    disk_usage(session_id, dst).await
+}
+
+/// `du -sk` 统计的磁盘占用（字节），失败时为 0
+async fn disk_usage(session_id: &str, path: &str) -> u64 {
+    let Ok(mut channel) = exec_channel(session_id, &format!("du -sk -- {} 2>/dev/null", shell_quote(path))).await else {
+        return 0;
+    };
+    let mut stdout: Vec<u8> = Vec::new();
+    loop {
+        match channel.wait().await {
+            Some(russh::ChannelMsg::Data { ref data }) => stdout.extend_from_slice(data),
+            Some(russh::ChannelMsg::Close) | None => break,
+            _ => {}
+        }
+    }
+    String::from_utf8_lossy(&stdout)
+        .split_whitespace()
+        .next()
+        .and_then(|kb| kb.parse::<u64>().ok())
+        .map(|kb| kb * 1024)
+        .unwrap_or(0)
+}
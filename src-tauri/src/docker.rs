@@ -0,0 +1,244 @@
+use crate::exec::{exec_channel, exec_output, shell_quote};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use std::collections::HashMap;
+use tauri::ipc::Channel;
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+/// 单次发送的最大行数，日志量大时分批发送
+const LOG_BATCH_LINES: usize = 500;
+
+/// 未遇到换行时缓存的最大字节数，超过后按一行发送
+const MAX_PENDING_BYTES: usize = 64 * 1024;
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DockerLogOptions {
+    /// 初始输出的行数（docker logs --tail），为空时输出全部
+    #[serde(default = "default_tail")]
+    pub tail: Option<u32>,
+    /// 持续跟踪新日志（docker logs -f）
+    #[serde(default = "default_follow")]
+    pub follow: bool,
+    /// 每行带时间戳
+    #[serde(default)]
+    pub timestamps: bool,
+    /// 只输出该时间之后的日志，如 "2024-01-02T15:04:05" 或 "10m"
+    #[serde(default)]
+    pub since: Option<String>,
+}
+
+fn default_tail() -> Option<u32> { Some(200) }
+fn default_follow() -> bool { true }
+
+#[derive(Clone, serde::Serialize)]
+#[serde(
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase",
+    tag = "event",
+    content = "data"
+)]
+pub enum DockerLogEvent {
+    /// stderr 为 true 时是容器写到标准错误的日志
+    Lines {
+        lines: Vec<String>,
+        stderr: bool,
+    },
+    /// docker logs 结束（非跟踪模式输出完毕，或容器被删除等）
+    Exited {
+        exit_status: u32,
+    },
+    Stopped,
+}
+
+struct LogTask {
+    session_id: String,
+    token: CancellationToken,
+}
+
+/// 日志跟踪任务（key: log_id）
+static LOG_TASKS: Lazy<Mutex<HashMap<String, LogTask>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 容器名或 ID 只允许 docker 自身接受的字符，避免被当作选项解析
+fn check_container(container: &str) -> Result<(), String> {
+    let valid = container
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphanumeric())
+        && container
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid container name: {}", container))
+    }
+}
+
+/// 启动、停止、重启、暂停或恢复容器
+#[tauri::command]
+pub async fn ssh_docker_control(session_id: String, container: String, action: String) -> Result<(), String> {
+    check_container(&container)?;
+    if !["start", "stop", "restart", "pause", "unpause"].contains(&action.as_str()) {
+        return Err(format!("Unsupported container action: {}", action));
+    }
+    let command = format!("docker {} {}", action, shell_quote(&container));
+    let output = exec_output(&session_id, &command).await?;
+    if output.success() {
+        Ok(())
+    } else {
+        Err(output.error_message())
+    }
+}
+
+/// 通过 exec 通道读取容器日志（docker logs），按行推送，直到调用 ssh_docker_logs_stop
+#[tauri::command]
+pub async fn ssh_docker_logs(
+    log_id: String,
+    session_id: String,
+    container: String,
+    options: DockerLogOptions,
+    on_log_event: Channel<DockerLogEvent>,
+) -> Result<(), String> {
+    check_container(&container)?;
+
+    let mut command = String::from("docker logs");
+    if options.follow {
+        command.push_str(" -f");
+    }
+    if options.timestamps {
+        command.push_str(" -t");
+    }
+    if let Some(tail) = options.tail {
+        command.push_str(&format!(" --tail {}", tail));
+    }
+    if let Some(since) = options.since.as_deref().filter(|s| !s.is_empty()) {
+        command.push_str(&format!(" --since {}", shell_quote(since)));
+    }
+    command.push(' ');
+    command.push_str(&shell_quote(&container));
+
+    let token = CancellationToken::new();
+    {
+        let mut map = LOG_TASKS.lock().await;
+        if map.contains_key(&log_id) {
+            return Err("Log task already exists".to_string());
+        }
+        map.insert(
+            log_id.clone(),
+            LogTask { session_id: session_id.clone(), token: token.clone() },
+        );
+    }
+
+    let mut channel = match exec_channel(&session_id, &command).await {
+        Ok(channel) => channel,
+        Err(e) => {
+            remove_task(&log_id, &token).await;
+            return Err(e);
+        }
+    };
+
+    tokio::spawn(async move {
+        let mut stdout = LineBuffer::default();
+        let mut stderr = LineBuffer::default();
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => {
+                    let _ = channel.close().await;
+                    break;
+                }
+                msg = channel.wait() => match msg {
+                    Some(russh::ChannelMsg::Data { ref data }) => {
+                        stdout.push(data, false, &on_log_event);
+                    }
+                    Some(russh::ChannelMsg::ExtendedData { ref data, ext: 1 }) => {
+                        stderr.push(data, true, &on_log_event);
+                    }
+                    Some(russh::ChannelMsg::ExitStatus { exit_status }) => {
+                        stdout.flush(false, &on_log_event);
+                        stderr.flush(true, &on_log_event);
+                        let _ = on_log_event.send(DockerLogEvent::Exited { exit_status });
+                    }
+                    Some(russh::ChannelMsg::Close) | None => break,
+                    _ => {}
+                },
+            }
+        }
+        stdout.flush(false, &on_log_event);
+        stderr.flush(true, &on_log_event);
+        remove_task(&log_id, &token).await;
+        let _ = on_log_event.send(DockerLogEvent::Stopped);
+    });
+    Ok(())
+}
+
+/// 任务结束时移除条目；已取消说明条目已被 stop 移除，同一 log_id 可能已被新任务使用
+async fn remove_task(log_id: &str, token: &CancellationToken) {
+    let mut map = LOG_TASKS.lock().await;
+    if !token.is_cancelled() {
+        map.remove(log_id);
+    }
+}
+
+#[tauri::command]
+pub async fn ssh_docker_logs_stop(log_id: String) -> Result<(), String> {
+    let mut map = LOG_TASKS.lock().await;
+
+    if let Some(task) = map.remove(&log_id) {
+        task.token.cancel();
+        Ok(())
+    } else {
+        Err("任务不存在".to_string())
+    }
+}
+
+/// 会话关闭时结束该会话的所有日志跟踪
+pub async fn close_session_logs(session_id: &str) {
+    let mut map = LOG_TASKS.lock().await;
+    map.retain(|_, task| {
+        if task.session_id == session_id {
+            task.token.cancel();
+            false
+        } else {
+            true
+        }
+    });
+}
+
+/// 按行切分输出，保留不完整的末行（避免截断多字节字符），末行过长时直接发送
+#[derive(Default)]
+struct LineBuffer {
+    pending: Vec<u8>,
+}
+
+impl LineBuffer {
+    fn push(&mut self, data: &[u8], stderr: bool, on_log_event: &Channel<DockerLogEvent>) {
+        self.pending.extend_from_slice(data);
+        if let Some(last) = self.pending.iter().rposition(|b| *b == b'\n') {
+            let complete: Vec<u8> = self.pending.drain(..=last).collect();
+            let text = String::from_utf8_lossy(&complete);
+            let lines: Vec<String> = text
+                .trim_end_matches('\n')
+                .split('\n')
+                .map(|line| line.trim_end_matches('\r').to_string())
+                .collect();
+            for batch in lines.chunks(LOG_BATCH_LINES) {
+                let _ = on_log_event.send(DockerLogEvent::Lines { lines: batch.to_vec(), stderr });
+            }
+        }
+        if self.pending.len() > MAX_PENDING_BYTES {
+            self.flush(stderr, on_log_event);
+        }
+    }
+
+    fn flush(&mut self, stderr: bool, on_log_event: &Channel<DockerLogEvent>) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let line = String::from_utf8_lossy(&self.pending).trim_end_matches('\r').to_string();
+        self.pending.clear();
+        let _ = on_log_event.send(DockerLogEvent::Lines { lines: vec![line], stderr });
+    }
+}
@@ -0,0 +1,363 @@
+use crate::sftp::{sftp_atomic_write, sftp_download_file, sha256_hex, SftpFileVersion, SAVE_CONFLICT};
+use crate::ssh::ssh_get_sftp;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tauri::ipc::Channel;
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+/// 本地文件变化的轮询间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Clone, serde::Serialize)]
+#[serde(
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase",
+    tag = "event",
+    content = "data"
+)]
+pub enum ExternalEditEvent {
+    /// 已下载到本地并启动编辑器
+    Opened { local_path: String },
+    /// 本地保存已回传到远端
+    Uploaded { size: u64, mtime: u32 },
+    /// 远端文件已被他人修改，本次保存未回传；可调用 ssh_external_edit_upload(force=true) 覆盖
+    Conflict { message: String },
+    Error { message: String },
+    Closed,
+}
+
+struct EditTask {
+    session_id: String,
+    remote_path: String,
+    local_path: PathBuf,
+    token: CancellationToken,
+    /// 远端文件的当前版本，每次回传成功后更新
+    version: Mutex<SftpFileVersion>,
+    on_event: Channel<ExternalEditEvent>,
+}
+
+enum EditEntry {
+    /// 正在下载或启动编辑器，先占用 edit_id，耗时操作不持有锁
+    Opening { session_id: String, token: CancellationToken },
+    Open(Arc<EditTask>),
+}
+
+impl EditEntry {
+    fn session_id(&self) -> &str {
+        match self {
+            EditEntry::Opening { session_id, .. } => session_id,
+            EditEntry::Open(task) => &task.session_id,
+        }
+    }
+}
+
+static EDIT_TASKS: Lazy<Mutex<HashMap<String, EditEntry>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 下载远端文件到本地临时目录并用外部编辑器打开，之后每次本地保存自动回传
+/// editor 为编辑器命令（如 `code --wait`、`"C:\Program Files\Notepad++\notepad++.exe"`），
+/// 参数中的 {file} 会替换为本地路径，未包含时追加到末尾；为空时使用系统默认程序打开
+#[tauri::command]
+pub async fn ssh_external_edit_open(
+    edit_id: String,
+    session_id: String,
+    remote_path: String,
+    editor: Option<String>,
+    on_event: Channel<ExternalEditEvent>,
+) -> Result<String, String> {
+    check_edit_id(&edit_id)?;
+    let token = CancellationToken::new();
+    {
+        let mut map = EDIT_TASKS.lock().await;
+        if map.contains_key(&edit_id) {
+            return Err("Edit task already exists".to_string());
+        }
+        map.insert(
+            edit_id.clone(),
+            EditEntry::Opening { session_id: session_id.clone(), token: token.clone() },
+        );
+    }
+
+    let dir = workspace_dir(&edit_id);
+    let file_name = remote_path
+        .rsplit('/')
+        .find(|s| !s.is_empty())
+        .unwrap_or("untitled");
+    let local_path = dir.join(file_name);
+
+    let opened = async {
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .map_err(|e| e.to_string())?;
+        let version = download(&session_id, &remote_path, &local_path, &token).await?;
+        launch_editor(editor.as_deref(), &local_path)?;
+        Ok::<_, String>(version)
+    }
+    .await;
+
+    let mut map = EDIT_TASKS.lock().await;
+    let version = match opened {
+        // 打开过程中被关闭（ssh_external_edit_close 或会话关闭）
+        Ok(_) if token.is_cancelled() => {
+            abandon(&mut map, &edit_id, &token).await;
+            return Err("Edit task closed".to_string());
+        }
+        Ok(version) => version,
+        Err(e) => {
+            abandon(&mut map, &edit_id, &token).await;
+            return Err(e);
+        }
+    };
+
+    let local_path_str = local_path.to_string_lossy().to_string();
+    let task = Arc::new(EditTask {
+        session_id,
+        remote_path,
+        local_path,
+        token,
+        version: Mutex::new(version),
+        on_event,
+    });
+    map.insert(edit_id, EditEntry::Open(task.clone()));
+    drop(map);
+
+    let _ = task.on_event.send(ExternalEditEvent::Opened {
+        local_path: local_path_str.clone(),
+    });
+    tokio::spawn(watch_local(task));
+
+    Ok(local_path_str)
+}
+
+/// 打开失败或被关闭时释放占用的 edit_id 并删除临时目录；
+/// 已取消说明条目已被移除，同一 edit_id 可能已被新的打开占用，此时保留其目录
+async fn abandon(map: &mut HashMap<String, EditEntry>, edit_id: &str, token: &CancellationToken) {
+    if !token.is_cancelled() {
+        map.remove(edit_id);
+    }
+    if !map.contains_key(edit_id) {
+        let _ = tokio::fs::remove_dir_all(workspace_dir(edit_id)).await;
+    }
+}
+
+/// 手动回传本地文件；force 为 true 时忽略远端冲突直接覆盖
+#[tauri::command]
+pub async fn ssh_external_edit_upload(edit_id: String, force: bool) -> Result<(), String> {
+    let task = match EDIT_TASKS.lock().await.get(&edit_id) {
+        Some(EditEntry::Open(task)) => task.clone(),
+        _ => return Err("任务不存在".to_string()),
+    };
+    upload(&task, force).await
+}
+
+/// 结束外部编辑：停止监听并删除本地临时文件
+#[tauri::command]
+pub async fn ssh_external_edit_close(edit_id: String) -> Result<(), String> {
+    let entry = EDIT_TASKS.lock().await.remove(&edit_id);
+    match entry {
+        Some(entry) => {
+            close_entry(&edit_id, entry).await;
+            Ok(())
+        }
+        None => Err("任务不存在".to_string()),
+    }
+}
+
+/// 会话关闭时结束该会话的所有外部编辑
+pub async fn close_session_edits(session_id: &str) {
+    let entries: Vec<(String, EditEntry)> = {
+        let mut map = EDIT_TASKS.lock().await;
+        let ids: Vec<String> = map
+            .iter()
+            .filter(|(_, entry)| entry.session_id() == session_id)
+            .map(|(id, _)| id.clone())
+            .collect();
+        ids.into_iter()
+            .filter_map(|id| map.remove(&id).map(|entry| (id, entry)))
+            .collect()
+    };
+    for (edit_id, entry) in entries {
+        close_entry(&edit_id, entry).await;
+    }
+}
+
+/// 正在打开的任务只取消，临时目录由打开流程清理
+async fn close_entry(edit_id: &str, entry: EditEntry) {
+    match entry {
+        EditEntry::Opening { token, .. } => token.cancel(),
+        EditEntry::Open(task) => {
+            task.token.cancel();
+            let _ = tokio::fs::remove_dir_all(workspace_dir(edit_id)).await;
+            let _ = task.on_event.send(ExternalEditEvent::Closed);
+        }
+    }
+}
+
+/// edit_id 会作为本地临时目录名，只允许字母、数字、`_` 和 `-`
+fn check_edit_id(edit_id: &str) -> Result<(), String> {
+    let valid = !edit_id.is_empty()
+        && edit_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid edit id: {}", edit_id))
+    }
+}
+
+fn workspace_dir(edit_id: &str) -> PathBuf {
+    std::env::temp_dir().join("zenssh-edit").join(edit_id)
+}
+
+async fn download(
+    session_id: &str,
+    remote_path: &str,
+    local_path: &Path,
+    token: &CancellationToken,
+) -> Result<SftpFileVersion, String> {
+    let sftp = ssh_get_sftp(session_id).await?;
+    let metadata = sftp.metadata(remote_path).await.map_err(|e| e.to_string())?;
+    sftp_download_file(&sftp, remote_path, local_path, token, &mut |_, _| {}).await?;
+    let data = tokio::fs::read(local_path).await.map_err(|e| e.to_string())?;
+    Ok(SftpFileVersion {
+        size: metadata.size.unwrap_or(data.len() as u64),
+        mtime: metadata.mtime.unwrap_or(0),
+        sha256: Some(sha256_hex(&data)),
+    })
+}
+
+/// 启动外部编辑器，不等待其退出（部分编辑器会立即返回）
+fn launch_editor(editor: Option<&str>, path: &Path) -> Result<(), String> {
+    let Some(editor) = editor.map(str::trim).filter(|e| !e.is_empty()) else {
+        return tauri_plugin_opener::open_path(path, None::<&str>).map_err(|e| e.to_string());
+    };
+
+    let file = path.to_string_lossy().to_string();
+    let mut parts = split_command(editor);
+    if parts.iter().any(|p| p.contains("{file}")) {
+        for part in parts.iter_mut() {
+            *part = part.replace("{file}", &file);
+        }
+    } else {
+        parts.push(file);
+    }
+
+    let mut child = std::process::Command::new(&parts[0])
+        .args(&parts[1..])
+        .spawn()
+        .map_err(|e| format!("Failed to launch editor {}: {}", parts[0], e))?;
+    // 回收子进程，避免僵尸进程
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+/// 按空白拆分命令行，双引号内的空白保留
+fn split_command(command: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in command.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    parts.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        parts.push(current);
+    }
+    parts
+}
+
+async fn local_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = tokio::fs::metadata(path).await.ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// 轮询本地文件，修改时间或大小变化且连续两次轮询一致后回传
+/// （避免读到编辑器写了一半的文件）
+async fn watch_local(task: Arc<EditTask>) {
+    let mut last = local_stamp(&task.local_path).await;
+    let mut pending = None;
+    loop {
+        tokio::select! {
+            _ = task.token.cancelled() => break,
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+        }
+        // 部分编辑器保存时先删除再重命名，文件可能短暂不存在
+        let Some(stamp) = local_stamp(&task.local_path).await else {
+            continue;
+        };
+        if Some(stamp) == last {
+            pending = None;
+            continue;
+        }
+        if pending != Some(stamp) {
+            pending = Some(stamp);
+            continue;
+        }
+        last = Some(stamp);
+        pending = None;
+        // 错误已通过事件通知前端
+        let _ = upload(&task, false).await;
+    }
+}
+
+async fn upload(task: &EditTask, force: bool) -> Result<(), String> {
+    let data = match tokio::fs::read(&task.local_path).await {
+        Ok(data) => data,
+        Err(e) => {
+            let message = e.to_string();
+            let _ = task.on_event.send(ExternalEditEvent::Error {
+                message: message.clone(),
+            });
+            return Err(message);
+        }
+    };
+
+    let mut version = task.version.lock().await;
+    let sha256 = sha256_hex(&data);
+    // 内容未变（如仅 touch）时无需回传
+    if !force && version.sha256.as_deref() == Some(sha256.as_str()) {
+        return Ok(());
+    }
+
+    match sftp_atomic_write(&task.session_id, &task.remote_path, &data, Some(&version), force).await {
+        Ok(saved) => {
+            *version = SftpFileVersion {
+                size: saved.size,
+                mtime: saved.mtime,
+                sha256: Some(saved.sha256),
+            };
+            let _ = task.on_event.send(ExternalEditEvent::Uploaded {
+                size: saved.size,
+                mtime: saved.mtime,
+            });
+            Ok(())
+        }
+        Err(message) => {
+            let event = if message.starts_with(SAVE_CONFLICT) {
+                ExternalEditEvent::Conflict {
+                    message: message.clone(),
+                }
+            } else {
+                ExternalEditEvent::Error {
+                    message: message.clone(),
+                }
+            };
+            let _ = task.on_event.send(event);
+            Err(message)
+        }
+    }
+}
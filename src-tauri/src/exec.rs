@@ -0,0 +1,79 @@
+use crate::ssh::ssh_get_handle;
+use russh::client::Msg;
+use russh::Channel;
+
+/// 将参数转义为 POSIX shell 的单引号字符串
+pub fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+/// 打开一个新的 session channel 并以 exec 模式执行命令（不请求 PTY）
+pub async fn exec_channel(session_id: &str, command: &str) -> Result<Channel<Msg>, String> {
+    let handle = ssh_get_handle(session_id)?;
+    let channel = handle
+        .channel_open_session()
+        .await
+        .map_err(|e| e.to_string())?;
+    channel
+        .exec(true, command.as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(channel)
+}
+
+/// 命令执行结果
+pub struct ExecOutput {
+    /// 被信号终止时为 None
+    pub exit_status: Option<u32>,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+impl ExecOutput {
+    pub fn success(&self) -> bool {
+        self.exit_status == Some(0)
+    }
+
+    pub fn stdout_text(&self) -> String {
+        String::from_utf8_lossy(&self.stdout).to_string()
+    }
+
+    /// 失败时的错误信息：优先使用 stderr，否则为退出码
+    pub fn error_message(&self) -> String {
+        let stderr = String::from_utf8_lossy(&self.stderr).trim().to_string();
+        match (stderr.is_empty(), self.exit_status) {
+            (false, _) => stderr,
+            (true, Some(status)) => format!("Command exited with status {}", status),
+            (true, None) => "Command terminated without exit status".to_string(),
+        }
+    }
+}
+
+/// 执行命令并等待结束，收集全部输出（适用于输出较少的短命令）
+pub async fn exec_output(session_id: &str, command: &str) -> Result<ExecOutput, String> {
+    let channel = exec_channel(session_id, command).await?;
+    Ok(wait_output(channel).await)
+}
+
+/// 等待已执行命令的 channel 结束，收集全部输出
+pub async fn wait_output(mut channel: Channel<Msg>) -> ExecOutput {
+    let mut output = ExecOutput {
+        exit_status: None,
+        stdout: Vec::new(),
+        stderr: Vec::new(),
+    };
+    loop {
+        match channel.wait().await {
+            Some(russh::ChannelMsg::Data { ref data }) => output.stdout.extend_from_slice(data),
+            Some(russh::ChannelMsg::ExtendedData { ref data, ext: 1 }) => {
+                output.stderr.extend_from_slice(data)
+            }
+            Some(russh::ChannelMsg::ExitStatus { exit_status }) => {
+                output.exit_status = Some(exit_status);
+            }
+            Some(russh::ChannelMsg::Close) | None => break,
+            _ => {}
+        }
+    }
+    output
+}
@@ -0,0 +1,240 @@
+use crate::monitor::{
+    new_alert_states, record_sample, resolve_alerts, run_monitor, ServerStats, MONITOR_UNSUPPORTED,
+};
+use crate::ssh::{ssh_connect_headless, ssh_disconnect_handle, ssh_get_config};
+use log::info;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
+use tauri::ipc::Channel;
+use tauri::AppHandle;
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+/// 重连等待时间，失败次数越多等待越久
+const RECONNECT_MIN: Duration = Duration::from_secs(5);
+const RECONNECT_MAX: Duration = Duration::from_secs(60);
+
+/// 单台主机的最新状态
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FleetHost {
+    pub config_id: String,
+    pub host: String,
+    /// "connecting"、"connected"、"error"，以及不再重连的 "disabled"（未开启监控）、"unsupported"（系统不支持）
+    pub status: String,
+    pub message: Option<String>,
+    pub stats: Option<ServerStats>,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase",
+    tag = "event",
+    content = "data"
+)]
+pub enum FleetEvent {
+    /// 主机连接状态变化；出错后会自动重连，retry_in_secs 为下次重连的等待时间
+    Status {
+        config_id: String,
+        status: String,
+        message: Option<String>,
+        retry_in_secs: Option<u64>,
+    },
+    Stats {
+        config_id: String,
+        stats: Box<ServerStats>,
+    },
+    Stopped,
+}
+
+struct FleetTask {
+    token: CancellationToken,
+    hosts: Arc<StdMutex<HashMap<String, FleetHost>>>,
+}
+
+/// 批量监控任务（key: fleet_id）
+static FLEET_TASKS: Lazy<Mutex<HashMap<String, FleetTask>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 监控历史、告警中代表该主机的会话ID，可用于 ssh_monitor_history / ssh_monitor_export
+fn fleet_session_id(fleet_id: &str, config_id: &str) -> String {
+    format!("fleet-{}-{}", fleet_id, config_id)
+}
+
+/// 不打开终端，同时监控多台已保存的主机（按各自的监控配置采集，跳板机按配置连接）
+/// 每台主机独立连接，断开或出错后自动重连，直到调用 ssh_fleet_monitor_stop
+#[tauri::command]
+pub async fn ssh_fleet_monitor_start(
+    app: AppHandle,
+    fleet_id: String,
+    config_ids: Vec<String>,
+    on_fleet_event: Channel<FleetEvent>,
+) -> Result<(), String> {
+    let mut map = FLEET_TASKS.lock().await;
+    if map.contains_key(&fleet_id) {
+        return Err("Fleet monitor already exists".to_string());
+    }
+
+    let mut configs = Vec::new();
+    for config_id in &config_ids {
+        let config = ssh_get_config(config_id).ok_or_else(|| format!("Config not found: {}", config_id))?;
+        if config.r#type == "serial" {
+            return Err(format!("Serial config cannot be monitored: {}", config_id));
+        }
+        config.monitor.validate().map_err(|e| format!("{}: {}", config_id, e))?;
+        configs.push(config);
+    }
+
+    let token = CancellationToken::new();
+    let hosts: Arc<StdMutex<HashMap<String, FleetHost>>> = Arc::new(StdMutex::new(
+        configs
+            .iter()
+            .map(|config| {
+                let status = if config.monitor.enabled { "connecting" } else { "disabled" };
+                let host = FleetHost {
+                    config_id: config.config_id.clone(),
+                    host: config.host.clone(),
+                    status: status.to_string(),
+                    message: None,
+                    stats: None,
+                };
+                (config.config_id.clone(), host)
+            })
+            .collect(),
+    ));
+
+    let mut workers = Vec::new();
+    for config in configs.into_iter().filter(|config| config.monitor.enabled) {
+        let app = app.clone();
+        let fleet_id = fleet_id.clone();
+        let token = token.clone();
+        let hosts = hosts.clone();
+        let on_fleet_event = on_fleet_event.clone();
+        workers.push(tokio::spawn(async move {
+            monitor_host(app, &fleet_id, config, &token, &hosts, &on_fleet_event).await;
+        }));
+    }
+    map.insert(fleet_id.clone(), FleetTask { token: token.clone(), hosts });
+
+    tokio::spawn(async move {
+        for worker in workers {
+            let _ = worker.await;
+        }
+        // 主机都不再重连时保留任务以便查看状态，直到 ssh_fleet_monitor_stop
+        token.cancelled().await;
+        // stop 时已移除本任务，表中有同名任务说明已被重新启动，不能清除新任务的历史
+        let map = FLEET_TASKS.lock().await;
+        if !map.contains_key(&fleet_id) {
+            for config_id in &config_ids {
+                crate::monitor::clear_history(&fleet_session_id(&fleet_id, config_id));
+            }
+        }
+        drop(map);
+        let _ = on_fleet_event.send(FleetEvent::Stopped);
+    });
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn ssh_fleet_monitor_stop(fleet_id: String) -> Result<(), String> {
+    let mut map = FLEET_TASKS.lock().await;
+
+    if let Some(task) = map.remove(&fleet_id) {
+        task.token.cancel();
+        Ok(())
+    } else {
+        Err("任务不存在".to_string())
+    }
+}
+
+/// 所有主机的最新状态（无序，前端自行排序）
+#[tauri::command]
+pub async fn ssh_fleet_monitor_snapshot(fleet_id: String) -> Result<Vec<FleetHost>, String> {
+    let map = FLEET_TASKS.lock().await;
+    let task = map.get(&fleet_id).ok_or("任务不存在")?;
+    let hosts = task.hosts.lock().unwrap();
+    Ok(hosts.values().cloned().collect())
+}
+
+/// 单台主机：连接 → 采集 → 出错后退避重连
+async fn monitor_host(
+    app: AppHandle,
+    fleet_id: &str,
+    config: crate::ssh::SshConfig,
+    token: &CancellationToken,
+    hosts: &StdMutex<HashMap<String, FleetHost>>,
+    on_fleet_event: &Channel<FleetEvent>,
+) {
+    let config_id = config.config_id.clone();
+    let session_id = fleet_session_id(fleet_id, &config_id);
+    let mut failures: u32 = 0;
+
+    let set_status = |status: &str, message: Option<String>, retry_in_secs: Option<u64>| {
+        if let Some(host) = hosts.lock().unwrap().get_mut(&config_id) {
+            host.status = status.to_string();
+            host.message = message.clone();
+        }
+        let _ = on_fleet_event.send(FleetEvent::Status {
+            config_id: config_id.clone(),
+            status: status.to_string(),
+            message,
+            retry_in_secs,
+        });
+    };
+
+    while !token.is_cancelled() {
+        set_status("connecting", None, None);
+        let connected = tokio::select! {
+            _ = token.cancelled() => break,
+            res = ssh_connect_headless(app.clone(), &config, session_id.clone()) => res,
+        };
+        let error = match connected {
+            Ok(handle) => {
+                set_status("connected", None, None);
+                let mut alert_states = new_alert_states(&config.monitor);
+                let mut received = false;
+                let result = run_monitor(&handle, &session_id, &config.monitor, token, |stats| {
+                    received = true;
+                    if let Some(host) = hosts.lock().unwrap().get_mut(&config_id) {
+                        host.stats = Some(stats.clone());
+                    }
+                    record_sample(&app, &session_id, &config.monitor, &mut alert_states, &stats);
+                    let _ = on_fleet_event.send(FleetEvent::Stats {
+                        config_id: config_id.clone(),
+                        stats: Box::new(stats),
+                    });
+                })
+                .await;
+                resolve_alerts(&app, &session_id, &config.monitor, &mut alert_states);
+                ssh_disconnect_handle(&handle).await;
+                // 采集到过数据说明连接可用，重新从最短等待开始重连
+                if received {
+                    failures = 0;
+                }
+                match result {
+                    Ok(()) => break,
+                    Err(e) if e.starts_with(MONITOR_UNSUPPORTED) => {
+                        set_status("unsupported", Some(e), None);
+                        break;
+                    }
+                    Err(e) => e,
+                }
+            }
+            Err(e) => e,
+        };
+
+        failures += 1;
+        let delay = RECONNECT_MIN
+            .saturating_mul(2u32.saturating_pow(failures - 1))
+            .min(RECONNECT_MAX);
+        info!("Fleet monitor {} failed: {}, retry in {:?}", session_id, error, delay);
+        set_status("error", Some(error), Some(delay.as_secs()));
+        tokio::select! {
+            _ = token.cancelled() => break,
+            _ = tokio::time::sleep(delay) => {}
+        }
+    }
+}
@@ -1,7 +1,25 @@
 mod encrypt;
 mod ssh;
 mod sftp;
+mod scp;
+mod exec;
+mod transfer;
+mod text;
+mod search;
+mod sync;
+mod archive;
+mod usage;
+mod checksum;
+mod copy;
+mod remove;
+mod watch;
+mod tail;
 mod monitor;
+mod fleet;
+mod docker;
+mod systemd;
+#[cfg(desktop)]
+mod editor;
 #[cfg(not(target_os = "ios"))]
 mod serial;
 
@@ -40,6 +58,7 @@ pub fn run() {
         ssh::sync_config,
         ssh::ssh_connect,
         ssh::ssh_respond_host_key,
+        ssh::sync_host_keys,
         // 会话管理
         ssh::ssh_close,
         ssh::ssh_run_command,
@@ -51,16 +70,80 @@ pub fn run() {
         sftp::ssh_sftp_read,
         sftp::ssh_sftp_read_cancel,
         sftp::ssh_sftp_read_text,
+        sftp::ssh_sftp_open_text,
+        sftp::ssh_sftp_read_text_range,
+        sftp::ssh_sftp_write_text,
+        sftp::ssh_sftp_save_text,
         sftp::ssh_sftp_create,
         sftp::ssh_sftp_write,
         sftp::ssh_sftp_write_chunk,
+        sftp::ssh_sftp_upload,
+        sftp::ssh_sftp_upload_cancel,
         sftp::ssh_sftp_mkdir,
-        sftp::ssh_sftp_remove_dir,
         sftp::ssh_sftp_remove_file,
+        remove::ssh_sftp_remove_dir,
+        remove::ssh_sftp_remove_cancel,
+        // 目录监听
+        watch::ssh_sftp_watch,
+        watch::ssh_sftp_watch_stop,
+        // 日志跟踪
+        tail::ssh_sftp_tail,
+        tail::ssh_sftp_tail_stop,
+        copy::ssh_sftp_copy,
+        copy::ssh_sftp_copy_cancel,
+        // 外部编辑器（桌面端）
+        #[cfg(desktop)]
+        editor::ssh_external_edit_open,
+        #[cfg(desktop)]
+        editor::ssh_external_edit_upload,
+        #[cfg(desktop)]
+        editor::ssh_external_edit_close,
+        // 远端搜索
+        search::ssh_sftp_search,
+        search::ssh_sftp_search_cancel,
+        search::ssh_remote_grep,
+        // 文件校验
+        checksum::ssh_sftp_checksum,
+        checksum::ssh_checksum_compare,
+        // 磁盘占用分析
+        usage::ssh_disk_usage,
+        usage::ssh_disk_usage_cancel,
+        // 目录同步
+        sync::ssh_sync_plan,
+        sync::ssh_sync_execute,
+        sync::ssh_sync_cancel,
+        // 远端打包/解压
+        archive::ssh_archive_download,
+        archive::ssh_archive_extract_upload,
+        archive::ssh_archive_cancel,
+        // SCP传输（服务端无 sftp 子系统时使用）
+        scp::ssh_scp_download,
+        scp::ssh_scp_upload,
+        scp::ssh_scp_cancel,
         // 端口转发
         ssh::ssh_port_forward,
         ssh::ssh_close_port_forward,
         ssh::ssh_list_port_forwards,
+        // 服务器监控
+        monitor::ssh_monitor_start,
+        monitor::ssh_monitor_stop,
+        monitor::ssh_monitor_reconfigure,
+        monitor::ssh_monitor_history,
+        monitor::ssh_monitor_export,
+        monitor::ssh_monitor_kill,
+        // 批量监控（不打开终端）
+        fleet::ssh_fleet_monitor_start,
+        fleet::ssh_fleet_monitor_stop,
+        fleet::ssh_fleet_monitor_snapshot,
+        // 容器管理
+        docker::ssh_docker_control,
+        docker::ssh_docker_logs,
+        docker::ssh_docker_logs_stop,
+        // systemd 服务管理
+        systemd::ssh_systemd_list_units,
+        systemd::ssh_systemd_status,
+        systemd::ssh_systemd_action,
+        systemd::ssh_sudo_respond,
         // 串口通讯
         #[cfg(not(target_os = "ios"))]
         serial::serial_list,
@@ -1,7 +1,470 @@
+use crate::exec::exec_output;
+use crate::ssh::ssh_get_handle;
 use log::{error, info};
-use serde::Serialize;
-use std::collections::HashMap;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write as _;
+use std::sync::Mutex as StdMutex;
+use std::time::Instant;
 use tauri::{AppHandle, Emitter};
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+/// 每个会话最多保留的历史样本数
+const MAX_HISTORY_SIZE: usize = 86400;
+
+/// 远端系统不支持监控时 run_monitor 返回的错误前缀
+pub const MONITOR_UNSUPPORTED: &str = "Monitor not supported";
+
+/// 可采集的指标分组
+const METRIC_GROUPS: &[&str] = &["cpu", "memory", "network", "disk", "process", "docker"];
+
+/// 默认采集的分组（docker stats 较慢且需要权限，需显式开启）
+const DEFAULT_METRIC_GROUPS: &[&str] = &["cpu", "memory", "network", "disk", "process"];
+
+/// 监控配置（随 SSH 连接配置保存）
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonitorConfig {
+    /// 是否在连接建立后自动启动监控
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 采集间隔（秒），范围 1 ~ 3600
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    /// 采集的指标分组: cpu、memory、network、disk、process、docker
+    #[serde(default = "default_metrics")]
+    pub metrics: Vec<String>,
+    /// 后端保留的历史样本数，0 表示不保留
+    #[serde(default = "default_history_size")]
+    pub history_size: usize,
+    /// 告警规则
+    #[serde(default)]
+    pub alert_rules: Vec<AlertRule>,
+}
+
+/// 告警可用的指标
+const ALERT_METRICS: &[&str] = &[
+    "cpuUsage", "memUsage", "memAvailableKb", "swapUsage", "load1", "load5", "load15",
+    "diskUsage", "inodeUsage", "netRxSpeed", "netTxSpeed",
+];
+
+/// 告警规则：指标持续 duration_secs 秒满足 `值 comparator threshold` 时触发
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertRule {
+    /// 规则ID（由前端生成）
+    pub id: String,
+    /// 见 ALERT_METRICS
+    pub metric: String,
+    /// 磁盘类指标为挂载点，网络类指标为接口名；为空时取所有挂载点的最大值 / 所有接口之和
+    #[serde(default)]
+    pub target: Option<String>,
+    /// ">"、">="、"<"、"<="
+    pub comparator: String,
+    pub threshold: f64,
+    #[serde(default)]
+    pub duration_secs: u64,
+}
+
+impl AlertRule {
+    fn validate(&self) -> Result<(), String> {
+        if !ALERT_METRICS.contains(&self.metric.as_str()) {
+            return Err(format!("Unknown alert metric: {}", self.metric));
+        }
+        if ![">", ">=", "<", "<="].contains(&self.comparator.as_str()) {
+            return Err(format!("Invalid alert comparator: {}", self.comparator));
+        }
+        Ok(())
+    }
+
+    /// 从样本中取规则对应的值；当前系统不支持或目标不存在时为 None
+    fn value(&self, stats: &ServerStats) -> Option<f64> {
+        let caps = &stats.capabilities;
+        let target = self.target.as_deref().filter(|t| !t.is_empty());
+        let filesystems = || {
+            stats
+                .filesystems
+                .iter()
+                .filter(move |fs| target.is_none_or(|t| fs.mount == t))
+        };
+        let interfaces = || {
+            stats
+                .net_interfaces
+                .iter()
+                .filter(move |iface| target.is_none_or(|t| iface.name == t))
+        };
+        let max = |values: Vec<f64>| values.into_iter().reduce(f64::max);
+        let sum = |values: Vec<f64>| if values.is_empty() { None } else { Some(values.iter().sum()) };
+        match self.metric.as_str() {
+            "cpuUsage" if caps.cpu_usage => Some(stats.cpu_usage),
+            "memUsage" if caps.memory => Some(stats.mem_usage),
+            "memAvailableKb" if caps.memory => Some(stats.mem_available_kb as f64),
+            "swapUsage" if caps.swap && stats.swap_total_kb > 0 => {
+                Some(100.0 * stats.swap_used_kb as f64 / stats.swap_total_kb as f64)
+            }
+            "load1" if caps.load_avg => Some(stats.load1),
+            "load5" if caps.load_avg => Some(stats.load5),
+            "load15" if caps.load_avg => Some(stats.load15),
+            "diskUsage" if caps.filesystems => max(filesystems().map(|fs| fs.usage).collect()),
+            "inodeUsage" if caps.inodes => max(filesystems().map(|fs| fs.inode_usage).collect()),
+            "netRxSpeed" if caps.network => sum(interfaces().map(|iface| iface.rx_speed).collect()),
+            "netTxSpeed" if caps.network => sum(interfaces().map(|iface| iface.tx_speed).collect()),
+            _ => None,
+        }
+    }
+
+    fn matches(&self, value: f64) -> bool {
+        match self.comparator.as_str() {
+            ">" => value > self.threshold,
+            ">=" => value >= self.threshold,
+            "<" => value < self.threshold,
+            "<=" => value <= self.threshold,
+            _ => false,
+        }
+    }
+}
+
+/// 告警事件（全局事件 ssh_monitor_alert）
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonitorAlert {
+    pub session_id: String,
+    pub rule_id: String,
+    pub metric: String,
+    pub target: Option<String>,
+    pub comparator: String,
+    pub threshold: f64,
+    pub value: f64,
+    /// "firing"（开始告警）或 "resolved"（恢复）
+    pub state: String,
+    pub timestamp: u64,
+}
+
+/// 单条规则的评估状态
+#[derive(Default)]
+pub struct AlertState {
+    /// 条件开始满足的时间
+    since: Option<u64>,
+    firing: bool,
+    /// 最近一次满足条件时的值
+    value: f64,
+}
+
+/// 一次监控运行中所有规则的评估状态
+pub struct AlertStates {
+    rules: Vec<AlertState>,
+    /// 已收到首个样本；首个样本没有上一轮数据，CPU 使用率和各项速率都为 0
+    baseline: bool,
+}
+
+/// 对每个样本评估告警规则，条件持续满足 duration_secs 后触发一次，条件不再满足时发送恢复
+fn evaluate_alerts(
+    session_id: &str,
+    rules: &[AlertRule],
+    states: &mut [AlertState],
+    stats: &ServerStats,
+) -> Vec<MonitorAlert> {
+    let mut alerts = Vec::new();
+    for (rule, state) in rules.iter().zip(states.iter_mut()) {
+        let value = rule.value(stats);
+        let matched = value.is_some_and(|value| rule.matches(value));
+        let alert = |state: &str| MonitorAlert {
+            session_id: session_id.to_string(),
+            rule_id: rule.id.clone(),
+            metric: rule.metric.clone(),
+            target: rule.target.clone(),
+            comparator: rule.comparator.clone(),
+            threshold: rule.threshold,
+            value: value.unwrap_or(0.0),
+            state: state.to_string(),
+            timestamp: stats.timestamp,
+        };
+        if !matched {
+            if state.firing {
+                alerts.push(alert("resolved"));
+            }
+            *state = AlertState::default();
+            continue;
+        }
+        state.value = value.unwrap_or(0.0);
+        let since = *state.since.get_or_insert(stats.timestamp);
+        if !state.firing && stats.timestamp.saturating_sub(since) >= rule.duration_secs {
+            state.firing = true;
+            alerts.push(alert("firing"));
+        }
+    }
+    alerts
+}
+
+/// 监控结束（停止、重新配置、断线）时为仍在告警的规则发送恢复
+pub fn resolve_alerts(app: &AppHandle, session_id: &str, config: &MonitorConfig, alert_states: &mut AlertStates) {
+    for (rule, state) in config.alert_rules.iter().zip(alert_states.rules.iter_mut()) {
+        if !state.firing {
+            continue;
+        }
+        let alert = MonitorAlert {
+            session_id: session_id.to_string(),
+            rule_id: rule.id.clone(),
+            metric: rule.metric.clone(),
+            target: rule.target.clone(),
+            comparator: rule.comparator.clone(),
+            threshold: rule.threshold,
+            value: state.value,
+            state: "resolved".to_string(),
+            timestamp: now_secs(),
+        };
+        if let Err(e) = app.emit("ssh_monitor_alert", &alert) {
+            error!("Failed to emit monitor alert: {}", e);
+        }
+        *state = AlertState::default();
+    }
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn default_enabled() -> bool { true }
+fn default_interval_secs() -> u64 { 1 }
+fn default_metrics() -> Vec<String> { DEFAULT_METRIC_GROUPS.iter().map(|m| m.to_string()).collect() }
+fn default_history_size() -> usize { 900 }
+
+impl Default for MonitorConfig {
+    fn default() -> Self {
+        MonitorConfig {
+            enabled: default_enabled(),
+            interval_secs: default_interval_secs(),
+            metrics: default_metrics(),
+            history_size: default_history_size(),
+            alert_rules: vec![],
+        }
+    }
+}
+
+impl MonitorConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=3600).contains(&self.interval_secs) {
+            return Err(format!("Invalid monitor interval: {}", self.interval_secs));
+        }
+        if let Some(metric) = self.metrics.iter().find(|m| !METRIC_GROUPS.contains(&m.as_str())) {
+            return Err(format!("Unknown metric group: {}", metric));
+        }
+        if self.history_size > MAX_HISTORY_SIZE {
+            return Err(format!("History size exceeds {}", MAX_HISTORY_SIZE));
+        }
+        self.alert_rules.iter().try_for_each(|rule| rule.validate())
+    }
+
+    fn has(&self, group: &str) -> bool {
+        self.metrics.iter().any(|m| m == group)
+    }
+
+    /// 按远端系统生成监控脚本，每轮输出以 ===STAT=== 开头
+    fn script(&self, os: RemoteOs) -> String {
+        let mut script = String::from("while true; do\necho \"===STAT===\"\n");
+        let mut section = |name: &str, command: &str| {
+            script.push_str(&format!("echo \"==={}===\"\n{}\n", name, command));
+        };
+        match os {
+            RemoteOs::Linux | RemoteOs::Busybox => {
+                // 进程 CPU 占用依赖总 CPU 滴答
+                if self.has("cpu") || self.has("process") {
+                    section("CPU", "grep '^cpu' /proc/stat");
+                }
+                if self.has("cpu") {
+                    section("LOADAVG", "cat /proc/loadavg");
+                    section("UPTIME", "cat /proc/uptime");
+                }
+                if self.has("memory") {
+                    section(
+                        "MEMINFO",
+                        "grep -E '^(MemTotal|MemFree|MemAvailable|Buffers|Cached|SwapTotal|SwapFree):' /proc/meminfo",
+                    );
+                }
+                if self.has("network") {
+                    section("NETDEV", "cat /proc/net/dev");
+                }
+                if self.has("disk") {
+                    if os == RemoteOs::Linux {
+                        section("DF", "df -PTlk -x tmpfs -x devtmpfs -x squashfs -x overlay 2>/dev/null");
+                        section("DFINODE", "df -Pil -x tmpfs -x devtmpfs -x squashfs -x overlay 2>/dev/null");
+                    } else {
+                        // busybox df 不支持 -T/-x/-l
+                        section("DF", "df -Pk 2>/dev/null");
+                    }
+                    section("DISKSTATS", "cat /proc/diskstats");
+                }
+                if self.has("docker") {
+                    section(
+                        "DOCKERPS",
+                        "docker ps -a --no-trunc --format '{{.ID}}\t{{.Names}}\t{{.Image}}\t{{.State}}\t{{.Status}}\t{{.RunningFor}}' 2>/dev/null",
+                    );
+                    section(
+                        "DOCKERSTATS",
+                        "docker stats --no-stream --no-trunc --format '{{.ID}}\t{{.CPUPerc}}\t{{.MemUsage}}\t{{.MemPerc}}\t{{.NetIO}}\t{{.BlockIO}}\t{{.PIDs}}' 2>/dev/null",
+                    );
+                }
+                if self.has("process") {
+                    section("PROCSTAT", "cat /proc/[0-9]*/stat 2>/dev/null");
+                    if os == RemoteOs::Linux {
+                        section("PS", "ps -eo pid=,user=,args= 2>/dev/null");
+                    } else {
+                        section("PS", "ps -o pid,user,args 2>/dev/null");
+                    }
+                }
+            }
+            RemoteOs::FreeBsd | RemoteOs::Darwin => {
+                if self.has("cpu") {
+                    if os == RemoteOs::FreeBsd {
+                        section("CPTIME", "sysctl -n kern.cp_time kern.cp_times");
+                    } else {
+                        // macOS 没有 CPU 时间计数，按各进程 %cpu 之和估算
+                        section("NCPU", "sysctl -n hw.ncpu");
+                        section("PSSUM", "ps -A -o %cpu=");
+                    }
+                    section("LOADAVG", "sysctl -n vm.loadavg");
+                    section("BOOTTIME", "sysctl -n kern.boottime; date +%s");
+                }
+                if self.has("memory") {
+                    if os == RemoteOs::FreeBsd {
+                        section(
+                            "SYSMEM",
+                            "sysctl hw.physmem hw.pagesize vm.stats.vm.v_free_count vm.stats.vm.v_inactive_count vm.stats.vm.v_cache_count",
+                        );
+                        section("SWAP", "swapinfo -k 2>/dev/null");
+                    } else {
+                        section("SYSMEM", "sysctl hw.memsize");
+                        section("VMSTAT", "vm_stat");
+                        section("SWAP", "sysctl -n vm.swapusage");
+                    }
+                }
+                if self.has("network") {
+                    section("NETSTAT", "netstat -ibn");
+                }
+                if self.has("disk") {
+                    section("DF", "df -Pkl 2>/dev/null");
+                    if os == RemoteOs::FreeBsd {
+                        section("IOSTAT", "iostat -Ix -d 2>/dev/null");
+                    }
+                }
+                if self.has("process") {
+                    section("PSCPU", "ps -axo pid=,user=,state=,rss=,%cpu=,%mem=,args=");
+                }
+            }
+            RemoteOs::Unsupported => {}
+        }
+        script.push_str(&format!("sleep {}\ndone\n", self.interval_secs));
+        script
+    }
+
+    /// 当前系统及指标分组下 ServerStats 中有效的字段
+    fn capabilities(&self, os: RemoteOs) -> MonitorCapabilities {
+        let (linux, bsd) = match os {
+            RemoteOs::Linux | RemoteOs::Busybox => (true, false),
+            RemoteOs::FreeBsd | RemoteOs::Darwin => (false, true),
+            RemoteOs::Unsupported => (false, false),
+        };
+        let freebsd = os == RemoteOs::FreeBsd;
+        let (cpu, memory, network, disk, process, docker) = (
+            self.has("cpu"),
+            self.has("memory"),
+            self.has("network"),
+            self.has("disk"),
+            self.has("process"),
+            self.has("docker"),
+        );
+        MonitorCapabilities {
+            cpu_usage: cpu && (linux || bsd),
+            cpu_per_core: cpu && (linux || freebsd),
+            cpu_breakdown: cpu && (linux || freebsd),
+            load_avg: cpu && (linux || bsd),
+            uptime: cpu && (linux || bsd),
+            memory: memory && (linux || bsd),
+            swap: memory && (linux || bsd),
+            network: network && (linux || bsd),
+            filesystems: disk && (linux || bsd),
+            filesystem_type: disk && os == RemoteOs::Linux,
+            inodes: disk && os == RemoteOs::Linux,
+            disk_io: disk && (linux || freebsd),
+            processes: process && (linux || bsd),
+            containers: docker && linux,
+        }
+    }
+}
+
+/// 远端系统类型，决定采集脚本和解析方式
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum RemoteOs {
+    /// GNU/Linux
+    Linux,
+    /// 使用 busybox 工具集的 Linux（OpenWrt 等嵌入式系统）
+    Busybox,
+    /// FreeBSD / DragonFly
+    FreeBsd,
+    /// macOS
+    Darwin,
+    Unsupported,
+}
+
+impl RemoteOs {
+    fn name(&self) -> &'static str {
+        match self {
+            RemoteOs::Linux => "linux",
+            RemoteOs::Busybox => "busybox",
+            RemoteOs::FreeBsd => "freebsd",
+            RemoteOs::Darwin => "darwin",
+            RemoteOs::Unsupported => "unsupported",
+        }
+    }
+}
+
+/// 通过 `uname -s` 判断远端系统；Linux 下 df 指向 busybox 时视为嵌入式系统
+async fn detect_os(
+    handle: &russh::client::Handle<crate::ssh::SshClient>,
+) -> Result<(RemoteOs, String), String> {
+    let mut channel = handle.channel_open_session().await.map_err(|e| e.to_string())?;
+    channel
+        .exec(true, "uname -s; readlink -f \"$(command -v df)\" 2>/dev/null".as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut output = Vec::new();
+    loop {
+        match channel.wait().await {
+            Some(russh::ChannelMsg::Data { ref data }) => output.extend_from_slice(data),
+            Some(russh::ChannelMsg::Close) | None => break,
+            _ => {}
+        }
+    }
+    let output = String::from_utf8_lossy(&output);
+    let mut lines = output.lines();
+    let uname = lines.next().unwrap_or("").trim().to_string();
+    let os = match uname.as_str() {
+        "Linux" if lines.next().is_some_and(|df| df.trim().ends_with("busybox")) => RemoteOs::Busybox,
+        "Linux" => RemoteOs::Linux,
+        "FreeBSD" | "DragonFly" => RemoteOs::FreeBsd,
+        "Darwin" => RemoteOs::Darwin,
+        _ => RemoteOs::Unsupported,
+    };
+    Ok((os, uname))
+}
+
+struct MonitorTask {
+    config: MonitorConfig,
+    token: CancellationToken,
+}
+
+/// 运行中的监控任务（key: session_id）
+static MONITOR_TASKS: Lazy<Mutex<HashMap<String, MonitorTask>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 最近的监控样本（key: session_id），重新配置监控时保留，会话关闭时清除
+static MONITOR_HISTORY: Lazy<StdMutex<HashMap<String, VecDeque<MonitorSample>>>> =
+    Lazy::new(|| StdMutex::new(HashMap::new()));
 
 /// 网络接口统计
 #[derive(Clone, Debug, Serialize)]
@@ -16,11 +479,138 @@ pub struct NetInterface {
     pub tx_speed: f64,
 }
 
+/// 文件系统使用情况（df -P）
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilesystemUsage {
+    pub filesystem: String,
+    pub fstype: String,
+    pub mount: String,
+    pub total_kb: u64,
+    pub used_kb: u64,
+    pub available_kb: u64,
+    pub usage: f64,
+    pub inodes_total: u64,
+    pub inodes_used: u64,
+    pub inode_usage: f64,
+}
+
+/// 磁盘设备 I/O 统计（/proc/diskstats）
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskIo {
+    pub name: String,
+    /// 累计读写字节数
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+    /// 累计完成的读写次数
+    pub reads: u64,
+    pub writes: u64,
+    /// 读写速率 (bytes/s)
+    pub read_speed: f64,
+    pub write_speed: f64,
+    /// 每秒读写次数
+    pub read_iops: f64,
+    pub write_iops: f64,
+}
+
+/// 进程信息（top 视图）
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub user: String,
+    pub command: String,
+    /// R/S/D/Z/T 等
+    pub state: String,
+    pub rss_kb: u64,
+    /// 与 top 一致，单核满载为 100
+    pub cpu_usage: f64,
+    pub mem_usage: f64,
+}
+
+/// 容器状态（docker ps / docker stats）
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContainerInfo {
+    /// 短 ID（12 位）
+    pub id: String,
+    pub name: String,
+    pub image: String,
+    /// running、exited、paused 等
+    pub state: String,
+    /// 如 "Up 3 hours"、"Exited (0) 2 days ago"
+    pub status: String,
+    /// 创建至今，如 "3 weeks ago"
+    pub running_for: String,
+    /// 以下统计只对运行中的容器有效；CPU 与 docker stats 一致，单核满载为 100
+    pub cpu_usage: f64,
+    pub mem_used_bytes: u64,
+    pub mem_limit_bytes: u64,
+    pub mem_usage: f64,
+    pub net_rx_bytes: u64,
+    pub net_tx_bytes: u64,
+    pub block_read_bytes: u64,
+    pub block_write_bytes: u64,
+    pub pids: u32,
+}
+
+/// CPU 时间占比（%），各项之和为 100
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CpuBreakdown {
+    pub user: f64,
+    pub nice: f64,
+    pub system: f64,
+    pub idle: f64,
+    pub iowait: f64,
+    pub irq: f64,
+    pub softirq: f64,
+    pub steal: f64,
+}
+
+/// ServerStats 中各字段在当前远端系统上是否有效
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonitorCapabilities {
+    pub cpu_usage: bool,
+    pub cpu_per_core: bool,
+    pub cpu_breakdown: bool,
+    pub load_avg: bool,
+    pub uptime: bool,
+    pub memory: bool,
+    pub swap: bool,
+    pub network: bool,
+    pub filesystems: bool,
+    /// FilesystemUsage.fstype
+    pub filesystem_type: bool,
+    /// FilesystemUsage 中的 inode 字段
+    pub inodes: bool,
+    pub disk_io: bool,
+    pub processes: bool,
+    pub containers: bool,
+}
+
 /// 服务器统计信息
 #[derive(Clone, Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ServerStats {
+    /// 远端系统: linux、busybox、freebsd、darwin、unsupported
+    pub os: String,
+    pub capabilities: MonitorCapabilities,
     pub cpu_usage: f64,
+    /// 逻辑核心数
+    pub cpu_cores: u32,
+    /// 每个核心的使用率，按 cpu0、cpu1... 排列
+    pub cpu_per_core: Vec<f64>,
+    /// 总体 CPU 时间占比
+    pub cpu_breakdown: CpuBreakdown,
+    /// 1/5/15 分钟平均负载
+    pub load1: f64,
+    pub load5: f64,
+    pub load15: f64,
+    /// 开机时长（秒）
+    pub uptime_secs: u64,
     pub mem_total_kb: u64,
     pub mem_used_kb: u64,
     pub mem_available_kb: u64,
@@ -28,13 +618,28 @@ pub struct ServerStats {
     pub swap_total_kb: u64,
     pub swap_used_kb: u64,
     pub net_interfaces: Vec<NetInterface>,
+    pub filesystems: Vec<FilesystemUsage>,
+    pub disk_io: Vec<DiskIo>,
+    /// CPU / 内存占用最高的进程
+    pub top_cpu: Vec<ProcessInfo>,
+    pub top_mem: Vec<ProcessInfo>,
+    pub containers: Vec<ContainerInfo>,
     pub timestamp: u64,
 }
 
 impl Default for ServerStats {
     fn default() -> Self {
         ServerStats {
+            os: String::new(),
+            capabilities: MonitorCapabilities::default(),
             cpu_usage: 0.0,
+            cpu_cores: 0,
+            cpu_per_core: vec![],
+            cpu_breakdown: CpuBreakdown::default(),
+            load1: 0.0,
+            load5: 0.0,
+            load15: 0.0,
+            uptime_secs: 0,
             mem_total_kb: 0,
             mem_used_kb: 0,
             mem_available_kb: 0,
@@ -42,6 +647,11 @@ impl Default for ServerStats {
             swap_total_kb: 0,
             swap_used_kb: 0,
             net_interfaces: vec![],
+            filesystems: vec![],
+            disk_io: vec![],
+            top_cpu: vec![],
+            top_mem: vec![],
+            containers: vec![],
             timestamp: 0,
         }
     }
@@ -70,13 +680,30 @@ impl CpuStats {
     }
 }
 
-/// 解析 /proc/stat 第一行
-fn parse_cpu_stat(line: &str) -> Option<CpuStats> {
+/// top 视图中每类进程的数量
+const TOP_PROCESSES: usize = 10;
+
+/// 允许通过 ssh_monitor_kill 发送的信号
+const KILL_SIGNALS: &[&str] = &["TERM", "KILL", "HUP", "INT", "QUIT", "STOP", "CONT", "USR1", "USR2"];
+
+/// 上一轮的 CPU 统计：总体及各核心
+#[derive(Clone, Default)]
+struct CpuSnapshot {
+    total: CpuStats,
+    cores: Vec<CpuStats>,
+}
+
+/// 解析 /proc/stat 的 cpu 行，返回核心编号（总体为 None）和统计
+fn parse_cpu_stat(line: &str) -> Option<(Option<usize>, CpuStats)> {
     let parts: Vec<&str> = line.split_whitespace().collect();
-    if parts.len() < 8 || parts[0] != "cpu" {
+    if parts.len() < 5 || !parts[0].starts_with("cpu") {
         return None;
     }
-    Some(CpuStats {
+    let core = match &parts[0][3..] {
+        "" => None,
+        index => Some(index.parse().ok()?),
+    };
+    Some((core, CpuStats {
         user: parts[1].parse().ok()?,
         nice: parts[2].parse().ok()?,
         system: parts[3].parse().ok()?,
@@ -85,21 +712,178 @@ fn parse_cpu_stat(line: &str) -> Option<CpuStats> {
         irq: parts.get(6).and_then(|s| s.parse().ok()).unwrap_or(0),
         softirq: parts.get(7).and_then(|s| s.parse().ok()).unwrap_or(0),
         steal: parts.get(8).and_then(|s| s.parse().ok()).unwrap_or(0),
-    })
+    }))
 }
 
 /// 计算 CPU 使用率
 fn calc_cpu_usage(prev: &CpuStats, curr: &CpuStats) -> f64 {
     let prev_total = prev.total();
     let curr_total = curr.total();
-    let total_diff = curr_total - prev_total;
+    let total_diff = curr_total.saturating_sub(prev_total);
     if total_diff == 0 {
         return 0.0;
     }
-    let idle_diff = curr.idle_total() - prev.idle_total();
+    let idle_diff = curr.idle_total().saturating_sub(prev.idle_total());
     100.0 * (1.0 - idle_diff as f64 / total_diff as f64)
 }
 
+/// 计算各项 CPU 时间占比
+fn calc_cpu_breakdown(prev: &CpuStats, curr: &CpuStats) -> CpuBreakdown {
+    let total_diff = curr.total().saturating_sub(prev.total());
+    if total_diff == 0 {
+        return CpuBreakdown::default();
+    }
+    let pct = |c: u64, p: u64| 100.0 * c.saturating_sub(p) as f64 / total_diff as f64;
+    CpuBreakdown {
+        user: pct(curr.user, prev.user),
+        nice: pct(curr.nice, prev.nice),
+        system: pct(curr.system, prev.system),
+        idle: pct(curr.idle, prev.idle),
+        iowait: pct(curr.iowait, prev.iowait),
+        irq: pct(curr.irq, prev.irq),
+        softirq: pct(curr.softirq, prev.softirq),
+        steal: pct(curr.steal, prev.steal),
+    }
+}
+
+/// 解析 /proc/loadavg: `0.00 0.01 0.05 1/123 4567`，或 sysctl vm.loadavg: `{ 0.00 0.01 0.05 }`
+fn parse_loadavg(text: &str) -> Option<(f64, f64, f64)> {
+    let mut parts = text.split_whitespace().filter(|part| *part != "{");
+    Some((
+        parts.next()?.parse().ok()?,
+        parts.next()?.parse().ok()?,
+        parts.next()?.parse().ok()?,
+    ))
+}
+
+/// 解析 /proc/uptime: `12345.67 23456.78`
+fn parse_uptime(text: &str) -> Option<u64> {
+    text.split_whitespace()
+        .next()?
+        .parse::<f64>()
+        .ok()
+        .map(|secs| secs as u64)
+}
+
+/// 解析 `sysctl -n kern.boottime; date +%s`: `{ sec = 1700000000, usec = 0 } ...` 与当前时间
+fn parse_boottime(text: &str) -> Option<u64> {
+    let boot: u64 = text
+        .split("sec = ")
+        .nth(1)?
+        .split(|c: char| !c.is_ascii_digit())
+        .next()?
+        .parse()
+        .ok()?;
+    let now: u64 = text.lines().nth(1)?.trim().parse().ok()?;
+    Some(now.saturating_sub(boot))
+}
+
+/// 解析 FreeBSD `sysctl -n kern.cp_time kern.cp_times`
+/// 第一行为总体，第二行依次为各核心，每组 5 项: user nice sys intr idle
+fn parse_cp_time(text: &str) -> Option<CpuSnapshot> {
+    let to_stats = |values: &[u64]| CpuStats {
+        user: values[0],
+        nice: values[1],
+        system: values[2],
+        irq: values[3],
+        idle: values[4],
+        ..Default::default()
+    };
+    let mut lines = text.lines();
+    let total: Vec<u64> = lines.next()?.split_whitespace().filter_map(|v| v.parse().ok()).collect();
+    if total.len() < 5 {
+        return None;
+    }
+    let cores: Vec<u64> = lines
+        .next()
+        .unwrap_or("")
+        .split_whitespace()
+        .filter_map(|v| v.parse().ok())
+        .collect();
+    Some(CpuSnapshot {
+        total: to_stats(&total),
+        cores: cores.chunks_exact(5).map(to_stats).collect(),
+    })
+}
+
+/// 解析 `sysctl name1 name2 ...` 的 `name: value` 输出
+fn parse_sysctl(text: &str) -> HashMap<String, u64> {
+    text.lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            Some((key.trim().to_string(), value.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+/// 解析 macOS `vm_stat`，返回 (页大小, 名称 -> 页数)
+fn parse_vm_stat(text: &str) -> (u64, HashMap<String, u64>) {
+    let page_size = text
+        .split("page size of ")
+        .nth(1)
+        .and_then(|rest| rest.split_whitespace().next())
+        .and_then(|size| size.parse().ok())
+        .unwrap_or(4096);
+    let pages = text
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            Some((key.trim().to_string(), value.trim().trim_end_matches('.').parse().ok()?))
+        })
+        .collect();
+    (page_size, pages)
+}
+
+/// 解析 FreeBSD `swapinfo -k`，返回 (总量, 已用) KB；多个交换设备时跳过 Total 汇总行
+fn parse_swapinfo(text: &str) -> (u64, u64) {
+    text.lines()
+        .skip(1)
+        .filter(|line| !line.starts_with("Total"))
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            Some((parts.get(1)?.parse::<u64>().ok()?, parts.get(2)?.parse::<u64>().ok()?))
+        })
+        .fold((0, 0), |(total, used), (t, u)| (total + t, used + u))
+}
+
+/// 解析 macOS `sysctl -n vm.swapusage`: `total = 2048.00M  used = 1049.25M  free = 998.75M`，返回 KB
+fn parse_swapusage(text: &str) -> (u64, u64) {
+    let value = |name: &str| -> u64 {
+        let raw = text
+            .split(&format!("{} = ", name))
+            .nth(1)
+            .and_then(|rest| rest.split_whitespace().next());
+        let Some(raw) = raw else {
+            return 0;
+        };
+        let (number, unit) = raw.split_at(raw.len().saturating_sub(1));
+        let number: f64 = number.parse().unwrap_or(0.0);
+        let kb = match unit {
+            "G" => number * 1024.0 * 1024.0,
+            "M" => number * 1024.0,
+            "K" => number,
+            _ => number / 1024.0,
+        };
+        kb as u64
+    };
+    (value("total"), value("used"))
+}
+
+/// 解析 BSD/macOS `netstat -ibn`，只取链路层行（Network 列为 <Link#N>）
+/// Address 列可能为空，因此 Ibytes/Obytes 从行尾倒数: ... Ibytes Opkts Oerrs Obytes Coll
+fn parse_netstat(text: &str) -> HashMap<String, (u64, u64)> {
+    let mut map = HashMap::new();
+    for line in text.lines().skip(1) {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() < 8 || !parts[2].starts_with("<Link") {
+            continue;
+        }
+        let from_end = |n: usize| parts[parts.len() - n].parse::<u64>().unwrap_or(0);
+        map.insert(parts[0].trim_end_matches('*').to_string(), (from_end(5), from_end(2)));
+    }
+    map
+}
+
 /// 解析 /proc/meminfo 行
 fn parse_meminfo(text: &str) -> HashMap<String, u64> {
     let mut map = HashMap::new();
@@ -135,91 +919,546 @@ fn parse_net_dev(text: &str) -> HashMap<String, (u64, u64)> {
     map
 }
 
-/// 解析一轮输出，返回 ServerStats
-fn parse_stats(
-    output: &str,
-    prev_cpu: &Option<CpuStats>,
-    prev_net: &HashMap<String, (u64, u64)>,
-    interval_secs: f64,
-) -> (ServerStats, Option<CpuStats>) {
-    let mut stats = ServerStats::default();
-    let mut new_cpu: Option<CpuStats> = None;
+/// 不统计的伪文件系统（df 不支持 -x 时在解析时过滤）
+const PSEUDO_FILESYSTEMS: &[&str] = &[
+    "tmpfs", "devtmpfs", "devfs", "fdescfs", "procfs", "linprocfs", "overlay", "none",
+];
 
-    // 分割各段
-    let stat_section = output
-        .split("===MEMINFO===")
-        .next()
-        .unwrap_or("")
-        .split("===STAT===")
-        .last()
-        .unwrap_or("");
-    let mem_section = output
-        .split("===NETDEV===")
-        .next()
-        .unwrap_or("")
-        .split("===MEMINFO===")
-        .last()
-        .unwrap_or("");
-    let net_section = output
-        .split("===STAT===")
-        .last()
-        .unwrap_or("")
-        .split("===NETDEV===")
-        .next()
-        .unwrap_or("");
+/// 解析 `df -PTk` 输出：Filesystem Type 1024-blocks Used Available Capacity Mounted on
+/// with_type 为 false 时为 `df -Pk` 输出，没有 Type 列
+fn parse_df(text: &str, with_type: bool) -> Vec<FilesystemUsage> {
+    let offset = if with_type { 1 } else { 0 };
+    text.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() < 6 + offset || PSEUDO_FILESYSTEMS.contains(&parts[0]) {
+                return None;
+            }
+            let total_kb: u64 = parts[1 + offset].parse().ok()?;
+            let used_kb: u64 = parts[2 + offset].parse().ok()?;
+            let available_kb: u64 = parts[3 + offset].parse().ok()?;
+            // 与 df 一致：已用 / (已用 + 可用)，不计入保留块
+            let usable = used_kb + available_kb;
+            Some(FilesystemUsage {
+                filesystem: parts[0].to_string(),
+                fstype: if with_type { parts[1].to_string() } else { String::new() },
+                // 挂载点可能含空格
+                mount: parts[5 + offset..].join(" "),
+                total_kb,
+                used_kb,
+                available_kb,
+                usage: if usable > 0 { 100.0 * used_kb as f64 / usable as f64 } else { 0.0 },
+                inodes_total: 0,
+                inodes_used: 0,
+                inode_usage: 0.0,
+            })
+        })
+        .filter(|fs| fs.total_kb > 0)
+        .collect()
+}
+
+/// 解析 `df -Pi` 输出，返回 挂载点 -> (inode 总数, 已用)
+fn parse_df_inodes(text: &str) -> HashMap<String, (u64, u64)> {
+    let mut map = HashMap::new();
+    for line in text.lines().skip(1) {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() < 6 {
+            continue;
+        }
+        if let (Ok(total), Ok(used)) = (parts[1].parse::<u64>(), parts[2].parse::<u64>()) {
+            map.insert(parts[5..].join(" "), (total, used));
+        }
+    }
+    map
+}
 
-    // 解析 CPU
-    for line in stat_section.lines() {
-        if let Some(cpu) = parse_cpu_stat(line) {
-            if let Some(prev) = prev_cpu {
-                stats.cpu_usage = calc_cpu_usage(prev, &cpu);
+/// 解析 /proc/diskstats，返回 设备名 -> (读次数, 读字节, 写次数, 写字节)，扇区固定为 512 字节
+/// 跳过 loop/ram 等虚拟设备以及已有整盘统计的分区
+fn parse_diskstats(text: &str) -> HashMap<String, (u64, u64, u64, u64)> {
+    let mut map = HashMap::new();
+    for line in text.lines() {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() < 10 {
+            continue;
+        }
+        let name = parts[2];
+        if ["loop", "ram", "zram", "sr", "fd"].iter().any(|p| name.starts_with(p)) {
+            continue;
+        }
+        let field = |i: usize| parts[i].parse::<u64>().unwrap_or(0);
+        map.insert(name.to_string(), (field(3), field(5) * 512, field(7), field(9) * 512));
+    }
+    // sda1 / nvme0n1p1 / mmcblk0p1 这类分区在整盘存在时去掉
+    let names: Vec<String> = map.keys().cloned().collect();
+    map.retain(|name, _| {
+        !names.iter().any(|disk| {
+            name.len() > disk.len()
+                && name.starts_with(disk.as_str())
+                && {
+                    let rest = &name[disk.len()..];
+                    let digits = rest.strip_prefix('p').unwrap_or(rest);
+                    !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+                }
+        })
+    });
+    map
+}
+
+/// 解析 FreeBSD `iostat -Ix -d`（累计值）: device r/i w/i kr/i kw/i ...
+/// 返回 设备名 -> (读次数, 读字节, 写次数, 写字节)
+fn parse_iostat(text: &str) -> HashMap<String, (u64, u64, u64, u64)> {
+    let mut map = HashMap::new();
+    for line in text.lines() {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() < 5 || ["pass", "cd"].iter().any(|p| parts[0].starts_with(p)) {
+            continue;
+        }
+        let field = |i: usize| parts[i].parse::<f64>().ok();
+        if let (Some(reads), Some(writes), Some(kr), Some(kw)) = (field(1), field(2), field(3), field(4)) {
+            map.insert(
+                parts[0].to_string(),
+                (reads as u64, (kr * 1024.0) as u64, writes as u64, (kw * 1024.0) as u64),
+            );
+        }
+    }
+    map
+}
+
+/// /proc/[pid]/stat 中的进程状态
+struct ProcStat {
+    comm: String,
+    state: String,
+    /// utime + stime（时钟滴答）
+    ticks: u64,
+    /// 常驻内存页数
+    rss_pages: u64,
+}
+
+/// 解析 `cat /proc/[0-9]*/stat`；comm 可能含空格和括号，以最后一个 ')' 为界
+fn parse_proc_stats(text: &str) -> HashMap<u32, ProcStat> {
+    let mut map = HashMap::new();
+    for line in text.lines() {
+        let (Some(open), Some(close)) = (line.find('('), line.rfind(')')) else {
+            continue;
+        };
+        if close < open {
+            continue;
+        }
+        let Ok(pid) = line[..open].trim().parse::<u32>() else {
+            continue;
+        };
+        // ')' 之后依次为 state(3) ppid(4) ... utime(14) stime(15) ... rss(24)
+        let fields: Vec<&str> = line[close + 1..].split_whitespace().collect();
+        if fields.len() < 22 {
+            continue;
+        }
+        let field = |i: usize| fields[i].parse::<u64>().unwrap_or(0);
+        map.insert(pid, ProcStat {
+            comm: line[open + 1..close].to_string(),
+            state: fields[0].to_string(),
+            ticks: field(11) + field(12),
+            rss_pages: field(21),
+        });
+    }
+    map
+}
+
+/// 解析 `ps -eo pid=,user=,args=`，返回 pid -> (用户, 完整命令行)
+fn parse_ps(text: &str) -> HashMap<u32, (String, String)> {
+    let mut map = HashMap::new();
+    for line in text.lines() {
+        let mut parts = line.split_whitespace();
+        let (Some(pid), Some(user)) = (parts.next(), parts.next()) else {
+            continue;
+        };
+        if let Ok(pid) = pid.parse::<u32>() {
+            map.insert(pid, (user.to_string(), parts.collect::<Vec<_>>().join(" ")));
+        }
+    }
+    map
+}
+
+/// 解析 BSD/macOS `ps -axo pid=,user=,state=,rss=,%cpu=,%mem=,args=`
+fn parse_ps_cpu(text: &str) -> Vec<ProcessInfo> {
+    text.lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let pid = parts.next()?.parse().ok()?;
+            let user = parts.next()?.to_string();
+            let state = parts.next()?.to_string();
+            let rss_kb = parts.next()?.parse().ok()?;
+            let cpu_usage = parts.next()?.parse().ok()?;
+            let mem_usage = parts.next()?.parse().ok()?;
+            Some(ProcessInfo {
+                pid,
+                user,
+                command: parts.collect::<Vec<_>>().join(" "),
+                state,
+                rss_kb,
+                cpu_usage,
+                mem_usage,
+            })
+        })
+        .collect()
+}
+
+/// 解析 docker 输出的容量: `1.5MiB`、`648B`、`1.2kB`、`7.6GiB`
+fn parse_docker_size(text: &str) -> u64 {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let number: f64 = text[..split].parse().unwrap_or(0.0);
+    let multiplier = match text[split..].trim() {
+        "kB" | "KB" => 1e3,
+        "MB" => 1e6,
+        "GB" => 1e9,
+        "TB" => 1e12,
+        "KiB" => 1024.0,
+        "MiB" => 1024.0 * 1024.0,
+        "GiB" => 1024.0 * 1024.0 * 1024.0,
+        "TiB" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        _ => 1.0,
+    };
+    (number * multiplier) as u64
+}
+
+/// 解析 docker 输出的 `已用 / 上限` 或 `接收 / 发送` 两个容量
+fn parse_docker_pair(text: &str) -> (u64, u64) {
+    match text.split_once('/') {
+        Some((a, b)) => (parse_docker_size(a), parse_docker_size(b)),
+        None => (parse_docker_size(text), 0),
+    }
+}
+
+/// 合并 docker ps 与 docker stats 的输出（均以制表符分隔，按完整 ID 关联）
+fn parse_docker(ps_text: &str, stats_text: &str) -> Vec<ContainerInfo> {
+    let stats: HashMap<&str, Vec<&str>> = stats_text
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() < 7 { None } else { Some((fields[0], fields)) }
+        })
+        .collect();
+    ps_text
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() < 6 {
+                return None;
+            }
+            let mut container = ContainerInfo {
+                id: fields[0].chars().take(12).collect(),
+                name: fields[1].to_string(),
+                image: fields[2].to_string(),
+                state: fields[3].to_string(),
+                status: fields[4].to_string(),
+                running_for: fields[5].to_string(),
+                cpu_usage: 0.0,
+                mem_used_bytes: 0,
+                mem_limit_bytes: 0,
+                mem_usage: 0.0,
+                net_rx_bytes: 0,
+                net_tx_bytes: 0,
+                block_read_bytes: 0,
+                block_write_bytes: 0,
+                pids: 0,
+            };
+            if let Some(stat) = stats.get(fields[0]) {
+                let percent = |text: &str| text.trim().trim_end_matches('%').parse().unwrap_or(0.0);
+                container.cpu_usage = percent(stat[1]);
+                (container.mem_used_bytes, container.mem_limit_bytes) = parse_docker_pair(stat[2]);
+                container.mem_usage = percent(stat[3]);
+                (container.net_rx_bytes, container.net_tx_bytes) = parse_docker_pair(stat[4]);
+                (container.block_read_bytes, container.block_write_bytes) = parse_docker_pair(stat[5]);
+                container.pids = stat[6].trim().parse().unwrap_or(0);
             }
-            new_cpu = Some(cpu);
-            break;
+            Some(container)
+        })
+        .collect()
+}
+
+/// 按 `===NAME===` 分隔行切分一轮输出；开头的 ===STAT=== 已在切分数据块时去掉
+fn split_sections(output: &str) -> HashMap<&str, String> {
+    let mut sections: HashMap<&str, String> = HashMap::new();
+    let mut current = "STAT";
+    for line in output.lines() {
+        let trimmed = line.trim();
+        if let Some(name) = trimmed
+            .strip_prefix("===")
+            .and_then(|rest| rest.strip_suffix("==="))
+        {
+            current = name;
+            continue;
         }
+        let section = sections.entry(current).or_default();
+        section.push_str(line);
+        section.push('\n');
     }
+    sections
+}
+
+/// 上一轮的累计值，用于计算差值
+#[derive(Default)]
+struct MonitorState {
+    cpu: Option<CpuSnapshot>,
+    net: HashMap<String, (u64, u64)>,
+    disk: HashMap<String, (u64, u64, u64, u64)>,
+    /// pid -> utime + stime
+    procs: HashMap<u32, u64>,
+    /// 上一轮数据的接收时间；docker stats 等命令耗时较长，速率按实际间隔计算
+    sampled: Option<Instant>,
+}
+
+/// 解析一轮输出，返回 ServerStats，并更新上一轮数据
+fn parse_stats(output: &str, os: RemoteOs, state: &mut MonitorState, received: Instant) -> ServerStats {
+    let mut stats = ServerStats {
+        os: os.name().to_string(),
+        ..Default::default()
+    };
+    let sections = split_sections(output);
+    let section = |name: &str| sections.get(name).map(|s| s.as_str()).unwrap_or("");
+    let is_linux = matches!(os, RemoteOs::Linux | RemoteOs::Busybox);
+    let elapsed_secs = state
+        .sampled
+        .replace(received)
+        .map(|prev| received.duration_since(prev).as_secs_f64())
+        .unwrap_or(0.0);
+    let rate = |curr: u64, prev: u64| {
+        if elapsed_secs > 0.0 {
+            curr.saturating_sub(prev) as f64 / elapsed_secs
+        } else {
+            0.0
+        }
+    };
+
+    // 解析 CPU（总体及各核心）
+    let snapshot = match os {
+        RemoteOs::Linux | RemoteOs::Busybox => {
+            let mut snapshot = CpuSnapshot::default();
+            let mut has_total = false;
+            for line in section("CPU").lines() {
+                match parse_cpu_stat(line) {
+                    Some((None, cpu)) => {
+                        snapshot.total = cpu;
+                        has_total = true;
+                    }
+                    Some((Some(_), cpu)) => snapshot.cores.push(cpu),
+                    None => {}
+                }
+            }
+            if has_total { Some(snapshot) } else { None }
+        }
+        RemoteOs::FreeBsd => parse_cp_time(section("CPTIME")),
+        _ => None,
+    };
+    let prev_cpu_total = state.cpu.as_ref().map(|prev| prev.total.total());
+    let cpu_total = snapshot.as_ref().map(|s| s.total.total()).unwrap_or(0);
+    if let Some(snapshot) = snapshot {
+        stats.cpu_cores = snapshot.cores.len() as u32;
+        if let Some(prev) = &state.cpu {
+            stats.cpu_usage = calc_cpu_usage(&prev.total, &snapshot.total);
+            stats.cpu_breakdown = calc_cpu_breakdown(&prev.total, &snapshot.total);
+            stats.cpu_per_core = snapshot
+                .cores
+                .iter()
+                .enumerate()
+                .map(|(i, core)| prev.cores.get(i).map(|p| calc_cpu_usage(p, core)).unwrap_or(0.0))
+                .collect();
+        } else {
+            stats.cpu_per_core = vec![0.0; snapshot.cores.len()];
+        }
+        state.cpu = Some(snapshot);
+    }
+    if os == RemoteOs::Darwin {
+        stats.cpu_cores = section("NCPU").trim().parse().unwrap_or(0);
+        let total: f64 = section("PSSUM").lines().filter_map(|l| l.trim().parse::<f64>().ok()).sum();
+        stats.cpu_usage = (total / stats.cpu_cores.max(1) as f64).min(100.0);
+    }
+
+    // 解析负载和运行时间
+    if let Some((load1, load5, load15)) = parse_loadavg(section("LOADAVG")) {
+        stats.load1 = load1;
+        stats.load5 = load5;
+        stats.load15 = load15;
+    }
+    stats.uptime_secs = if is_linux {
+        parse_uptime(section("UPTIME"))
+    } else {
+        parse_boottime(section("BOOTTIME"))
+    }
+    .unwrap_or(0);
 
     // 解析内存
-    let mem = parse_meminfo(mem_section);
-    stats.mem_total_kb = *mem.get("MemTotal").unwrap_or(&0);
-    stats.mem_available_kb = *mem.get("MemAvailable").unwrap_or(&0);
+    match os {
+        RemoteOs::Linux | RemoteOs::Busybox => {
+            let mem = parse_meminfo(section("MEMINFO"));
+            stats.mem_total_kb = *mem.get("MemTotal").unwrap_or(&0);
+            // 老内核没有 MemAvailable
+            stats.mem_available_kb = mem.get("MemAvailable").copied().unwrap_or_else(|| {
+                ["MemFree", "Buffers", "Cached"].iter().filter_map(|k| mem.get(*k)).sum()
+            });
+            stats.swap_total_kb = *mem.get("SwapTotal").unwrap_or(&0);
+            stats.swap_used_kb = stats.swap_total_kb.saturating_sub(*mem.get("SwapFree").unwrap_or(&0));
+        }
+        RemoteOs::FreeBsd => {
+            let mem = parse_sysctl(section("SYSMEM"));
+            let page_kb = mem.get("hw.pagesize").copied().unwrap_or(4096) / 1024;
+            stats.mem_total_kb = mem.get("hw.physmem").copied().unwrap_or(0) / 1024;
+            let free_pages: u64 = ["v_free_count", "v_inactive_count", "v_cache_count"]
+                .iter()
+                .filter_map(|k| mem.get(&format!("vm.stats.vm.{}", k)))
+                .sum();
+            stats.mem_available_kb = free_pages * page_kb;
+            (stats.swap_total_kb, stats.swap_used_kb) = parse_swapinfo(section("SWAP"));
+        }
+        RemoteOs::Darwin => {
+            let mem = parse_sysctl(section("SYSMEM"));
+            let (page_size, pages) = parse_vm_stat(section("VMSTAT"));
+            stats.mem_total_kb = mem.get("hw.memsize").copied().unwrap_or(0) / 1024;
+            stats.mem_available_kb = ["Pages free", "Pages inactive", "Pages speculative"]
+                .iter()
+                .filter_map(|k| pages.get(*k))
+                .sum::<u64>()
+                * page_size
+                / 1024;
+            (stats.swap_total_kb, stats.swap_used_kb) = parse_swapusage(section("SWAP"));
+        }
+        RemoteOs::Unsupported => {}
+    }
     stats.mem_used_kb = stats.mem_total_kb.saturating_sub(stats.mem_available_kb);
-    stats.swap_total_kb = *mem.get("SwapTotal").unwrap_or(&0);
-    stats.swap_used_kb = stats.swap_total_kb.saturating_sub(*mem.get("SwapFree").unwrap_or(&0));
     if stats.mem_total_kb > 0 {
         stats.mem_usage = 100.0 * stats.mem_used_kb as f64 / stats.mem_total_kb as f64;
     }
 
     // 解析网络
-    let curr_net = parse_net_dev(net_section);
-    for (name, (rx, tx)) in &curr_net {
-        let prev_rx = prev_net.get(name).map(|(r, _)| *r).unwrap_or(*rx);
-        let prev_tx = prev_net.get(name).map(|(_, t)| *t).unwrap_or(*tx);
-        let rx_speed = if interval_secs > 0.0 {
-            (*rx as f64 - prev_rx as f64) / interval_secs
-        } else {
-            0.0
-        };
-        let tx_speed = if interval_secs > 0.0 {
-            (*tx as f64 - prev_tx as f64) / interval_secs
-        } else {
-            0.0
-        };
+    let curr_net = if is_linux {
+        parse_net_dev(section("NETDEV"))
+    } else {
+        parse_netstat(section("NETSTAT"))
+    };
+    for (name, &(rx, tx)) in &curr_net {
+        let (prev_rx, prev_tx) = state.net.get(name).copied().unwrap_or((rx, tx));
         stats.net_interfaces.push(NetInterface {
             name: name.clone(),
-            rx_bytes: *rx,
-            tx_bytes: *tx,
-            rx_speed: rx_speed.max(0.0),
-            tx_speed: tx_speed.max(0.0),
+            rx_bytes: rx,
+            tx_bytes: tx,
+            rx_speed: rate(rx, prev_rx),
+            tx_speed: rate(tx, prev_tx),
         });
     }
 
-    stats.timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map(|d| d.as_secs())
-        .unwrap_or(0);
+    // 解析文件系统
+    let inodes = parse_df_inodes(section("DFINODE"));
+    stats.filesystems = parse_df(section("DF"), os == RemoteOs::Linux);
+    for fs in stats.filesystems.iter_mut() {
+        if let Some(&(total, used)) = inodes.get(&fs.mount) {
+            fs.inodes_total = total;
+            fs.inodes_used = used;
+            if total > 0 {
+                fs.inode_usage = 100.0 * used as f64 / total as f64;
+            }
+        }
+    }
+
+    // 解析磁盘 I/O
+    let curr_disk = if is_linux {
+        parse_diskstats(section("DISKSTATS"))
+    } else {
+        parse_iostat(section("IOSTAT"))
+    };
+    for (name, &(reads, read_bytes, writes, write_bytes)) in &curr_disk {
+        let (prev_reads, prev_read_bytes, prev_writes, prev_write_bytes) =
+            state.disk.get(name).copied().unwrap_or((reads, read_bytes, writes, write_bytes));
+        stats.disk_io.push(DiskIo {
+            name: name.clone(),
+            read_bytes,
+            write_bytes,
+            reads,
+            writes,
+            read_speed: rate(read_bytes, prev_read_bytes),
+            write_speed: rate(write_bytes, prev_write_bytes),
+            read_iops: rate(reads, prev_reads),
+            write_iops: rate(writes, prev_writes),
+        });
+    }
+    stats.disk_io.sort_by(|a, b| a.name.cmp(&b.name));
+    state.net = curr_net;
+    state.disk = curr_disk;
+
+    // 解析进程
+    let mut processes = if is_linux {
+        parse_linux_processes(
+            section("PROCSTAT"),
+            section("PS"),
+            state,
+            prev_cpu_total.map(|prev| cpu_total.saturating_sub(prev)).unwrap_or(0),
+            stats.cpu_cores,
+            stats.mem_total_kb,
+        )
+    } else {
+        parse_ps_cpu(section("PSCPU"))
+    };
+    processes.sort_by(|a, b| b.cpu_usage.total_cmp(&a.cpu_usage));
+    stats.top_cpu = processes.iter().take(TOP_PROCESSES).cloned().collect();
+    processes.sort_by_key(|p| std::cmp::Reverse(p.rss_kb));
+    stats.top_mem = processes.into_iter().take(TOP_PROCESSES).collect();
+
+    // 解析容器
+    stats.containers = parse_docker(section("DOCKERPS"), section("DOCKERSTATS"));
+
+    stats.timestamp = now_secs();
 
-    (stats, new_cpu)
+    stats
+}
+
+/// Linux 进程：CPU 占用为进程滴答差值占总 CPU 滴答差值的比例，再乘以核心数
+fn parse_linux_processes(
+    proc_text: &str,
+    ps_text: &str,
+    state: &mut MonitorState,
+    total_diff: u64,
+    cpu_cores: u32,
+    mem_total_kb: u64,
+) -> Vec<ProcessInfo> {
+    let procs = parse_proc_stats(proc_text);
+    let ps = parse_ps(ps_text);
+    let cores = cpu_cores.max(1) as f64;
+    let processes = procs
+        .iter()
+        .map(|(&pid, proc_stat)| {
+            let cpu_usage = match state.procs.get(&pid) {
+                Some(&prev) if total_diff > 0 => {
+                    100.0 * cores * proc_stat.ticks.saturating_sub(prev) as f64 / total_diff as f64
+                }
+                _ => 0.0,
+            };
+            // 内核线程没有命令行，使用 [comm]
+            let (user, command) = match ps.get(&pid) {
+                Some((user, args)) if !args.is_empty() => (user.clone(), args.clone()),
+                Some((user, _)) => (user.clone(), format!("[{}]", proc_stat.comm)),
+                None => (String::new(), format!("[{}]", proc_stat.comm)),
+            };
+            // 按 4KB 页大小换算
+            let rss_kb = proc_stat.rss_pages * 4;
+            ProcessInfo {
+                pid,
+                user,
+                command,
+                state: proc_stat.state.clone(),
+                rss_kb,
+                cpu_usage,
+                mem_usage: if mem_total_kb > 0 {
+                    100.0 * rss_kb as f64 / mem_total_kb as f64
+                } else {
+                    0.0
+                },
+            }
+        })
+        .collect();
+    state.procs = procs.into_iter().map(|(pid, proc_stat)| (pid, proc_stat.ticks)).collect();
+    processes
 }
 
 /// 在 buffer 中查找 marker 的位置
@@ -230,62 +1469,196 @@ fn find_marker(buffer: &[u8], marker: &[u8]) -> Option<usize> {
     buffer.windows(marker.len()).position(|w| w == marker)
 }
 
-/// 启动监控通道
-/// 在 SSH 连接建立后调用此方法，创建一个旁路 channel 持续读取服务器状态
-pub async fn start_monitor(
+/// 按配置启动监控（已有任务时先停止）；配置为禁用时只记录配置
+pub async fn spawn_monitor(
     app: AppHandle,
     handle: std::sync::Arc<russh::client::Handle<crate::ssh::SshClient>>,
     session_id: String,
-    shutdown_rx: tokio::sync::watch::Receiver<bool>,
+    config: MonitorConfig,
 ) {
-    let interval_secs = 1.0f64;
-
-    // 打开一个新的 session channel
-    let mut channel = match handle.channel_open_session().await {
-        Ok(c) => c,
+    // 保存的配置可能来自旧版本或被手工修改（如 interval_secs 为 0 会在远端空转），无效时使用默认配置
+    let config = match config.validate() {
+        Ok(()) => config,
         Err(e) => {
-            error!("Failed to open monitor channel for {}: {}", session_id, e);
-            return;
+            error!("Invalid monitor config for session {}: {}, using defaults", session_id, e);
+            MonitorConfig { enabled: config.enabled, ..MonitorConfig::default() }
         }
     };
-
-    // 执行监控脚本（不请求 PTY，使用 exec 模式）
-    let script = r#"while true; do
-echo "===STAT==="
-head -1 /proc/stat
-echo "===MEMINFO==="
-grep -E '^(MemTotal|MemFree|MemAvailable|Buffers|Cached|SwapTotal|SwapFree):' /proc/meminfo
-echo "===NETDEV==="
-cat /proc/net/dev
-sleep 1
-done
-"#;
-
-    if let Err(e) = channel.exec(true, script.as_bytes()).await {
-        error!("Failed to exec monitor script for {}: {}", session_id, e);
+    let token = CancellationToken::new();
+    let previous = MONITOR_TASKS.lock().await.insert(
+        session_id.clone(),
+        MonitorTask { config: config.clone(), token: token.clone() },
+    );
+    if let Some(previous) = previous {
+        previous.token.cancel();
+    }
+    if !config.enabled {
+        token.cancel();
         return;
     }
+    tokio::spawn(async move {
+        start_monitor(app, handle, session_id, config, token).await;
+    });
+}
+
+/// 会话关闭时停止监控并清除配置和历史
+pub async fn stop_session_monitor(session_id: &str) {
+    if let Some(task) = MONITOR_TASKS.lock().await.remove(session_id) {
+        task.token.cancel();
+    }
+    clear_history(session_id);
+}
+
+/// 在已连接的会话上启动监控；config 为空时沿用该会话当前的配置
+#[tauri::command]
+pub async fn ssh_monitor_start(
+    app: AppHandle,
+    session_id: String,
+    config: Option<MonitorConfig>,
+) -> Result<(), String> {
+    let handle = ssh_get_handle(&session_id)?;
+    let mut config = match config {
+        Some(config) => config,
+        None => MONITOR_TASKS
+            .lock()
+            .await
+            .get(&session_id)
+            .map(|task| task.config.clone())
+            .unwrap_or_default(),
+    };
+    config.validate()?;
+    config.enabled = true;
+    spawn_monitor(app, handle, session_id, config).await;
+    Ok(())
+}
+
+/// 停止会话的监控（保留配置，可再次 start）
+#[tauri::command]
+pub async fn ssh_monitor_stop(session_id: String) -> Result<(), String> {
+    let mut map = MONITOR_TASKS.lock().await;
+
+    if let Some(task) = map.get_mut(&session_id) {
+        task.token.cancel();
+        task.config.enabled = false;
+        Ok(())
+    } else {
+        Err("任务不存在".to_string())
+    }
+}
+
+/// 修改运行中会话的监控配置，按新配置重启（enabled 为 false 时停止）
+#[tauri::command]
+pub async fn ssh_monitor_reconfigure(
+    app: AppHandle,
+    session_id: String,
+    config: MonitorConfig,
+) -> Result<(), String> {
+    config.validate()?;
+    let handle = ssh_get_handle(&session_id)?;
+    trim_history(&session_id, config.history_size);
+    spawn_monitor(app, handle, session_id, config).await;
+    Ok(())
+}
+
+/// 会话的监控通道：推送 ssh_monitor_{session_id} 事件，评估告警并记录历史
+async fn start_monitor(
+    app: AppHandle,
+    handle: std::sync::Arc<russh::client::Handle<crate::ssh::SshClient>>,
+    session_id: String,
+    config: MonitorConfig,
+    token: CancellationToken,
+) {
+    let event_name = format!("ssh_monitor_{}", session_id);
+    let mut alert_states = new_alert_states(&config);
+    let result = run_monitor(&handle, &session_id, &config, &token, |stats| {
+        // 发送到前端
+        if let Err(e) = app.emit(&event_name, &stats) {
+            error!("Failed to emit monitor event: {}", e);
+        }
+        record_sample(&app, &session_id, &config, &mut alert_states, &stats);
+    })
+    .await;
+    resolve_alerts(&app, &session_id, &config, &mut alert_states);
+    if let Err(e) = result {
+        info!("Monitor stopped for session {}: {}", session_id, e);
+    }
+}
+
+pub fn new_alert_states(config: &MonitorConfig) -> AlertStates {
+    AlertStates {
+        rules: config.alert_rules.iter().map(|_| AlertState::default()).collect(),
+        baseline: false,
+    }
+}
+
+/// 评估告警（发送全局 ssh_monitor_alert 事件）并记入历史
+pub fn record_sample(
+    app: &AppHandle,
+    session_id: &str,
+    config: &MonitorConfig,
+    alert_states: &mut AlertStates,
+    stats: &ServerStats,
+) {
+    // 首个样本只作为差值基准，不参与告警评估
+    if alert_states.baseline {
+        for alert in evaluate_alerts(session_id, &config.alert_rules, &mut alert_states.rules, stats) {
+            if let Err(e) = app.emit("ssh_monitor_alert", &alert) {
+                error!("Failed to emit monitor alert: {}", e);
+            }
+        }
+    }
+    alert_states.baseline = true;
+    record_history(session_id, stats, config.history_size);
+}
+
+/// 在连接上打开旁路 channel 持续采集服务器状态，每轮样本交给 on_stats
+/// token 取消时返回 Ok，通道异常结束或系统不受支持时返回 Err
+pub async fn run_monitor(
+    handle: &russh::client::Handle<crate::ssh::SshClient>,
+    session_id: &str,
+    config: &MonitorConfig,
+    token: &CancellationToken,
+    mut on_stats: impl FnMut(ServerStats),
+) -> Result<(), String> {
+    let (os, uname) = detect_os(handle)
+        .await
+        .map_err(|e| format!("Failed to detect remote os: {}", e))?;
+    let capabilities = config.capabilities(os);
+    if os == RemoteOs::Unsupported {
+        // 发送一次空数据，前端据此提示不支持
+        on_stats(ServerStats { os: os.name().to_string(), capabilities, ..Default::default() });
+        return Err(format!("{} on {}", MONITOR_UNSUPPORTED, uname));
+    }
+
+    // 打开一个新的 session channel
+    let mut channel = handle
+        .channel_open_session()
+        .await
+        .map_err(|e| format!("Failed to open monitor channel: {}", e))?;
+
+    // 执行监控脚本（不请求 PTY，使用 exec 模式）
+    channel
+        .exec(true, config.script(os).as_bytes())
+        .await
+        .map_err(|e| format!("Failed to exec monitor script: {}", e))?;
 
-    info!("Monitor channel started for session: {}", session_id);
+    info!("Monitor channel started for session: {} ({})", session_id, uname);
 
-    // 本地状态：累积缓冲区、上一轮 CPU/网络数据
+    // 本地状态：累积缓冲区、上一轮累计数据
     let mut buffer: Vec<u8> = Vec::new();
-    let mut prev_cpu: Option<CpuStats> = None;
-    let mut prev_net: HashMap<String, (u64, u64)> = HashMap::new();
-    let mut shutdown_rx_mut = shutdown_rx;
+    let mut state = MonitorState::default();
 
     loop {
         tokio::select! {
-            _ = shutdown_rx_mut.changed() => {
-                if *shutdown_rx_mut.borrow() {
-                    info!("Monitor channel stopping for session: {}", session_id);
-                    let _ = channel.close().await;
-                    break;
-                }
+            _ = token.cancelled() => {
+                info!("Monitor channel stopping for session: {}", session_id);
+                let _ = channel.close().await;
+                return Ok(());
             }
             msg = channel.wait() => {
                 match msg {
                     Some(russh::ChannelMsg::Data { ref data }) => {
+                        let received = Instant::now();
                         buffer.extend_from_slice(data);
 
                         // 按 ===STAT=== 分隔符切分，处理完整的数据块
@@ -303,35 +1676,19 @@ done
                                 continue;
                             }
 
-                            let (stats, new_cpu) = parse_stats(&chunk, &prev_cpu, &prev_net, interval_secs);
-
-                            // 保存上一轮数据用于计算差值
-                            if let Some(ref cpu) = new_cpu {
-                                prev_cpu = Some(cpu.clone());
-                            }
-                            // 保存网络数据
-                            for iface in &stats.net_interfaces {
-                                prev_net.insert(iface.name.clone(), (iface.rx_bytes, iface.tx_bytes));
-                            }
-
-                            // 发送到前端
-                            let event_name = format!("ssh_monitor_{}", session_id);
-                            if let Err(e) = app.emit(&event_name, &stats) {
-                                error!("Failed to emit monitor event: {}", e);
-                            }
+                            let mut stats = parse_stats(&chunk, os, &mut state, received);
+                            stats.capabilities = capabilities.clone();
+                            on_stats(stats);
                         }
                     }
                     Some(russh::ChannelMsg::Eof) | Some(russh::ChannelMsg::Close) => {
-                        info!("Monitor channel closed for session: {}", session_id);
-                        break;
+                        return Err("Monitor channel closed".to_string());
                     }
                     Some(russh::ChannelMsg::ExitStatus { exit_status }) => {
-                        info!("Monitor script exited with status: {} for session: {}", exit_status, session_id);
-                        break;
+                        return Err(format!("Monitor script exited with status: {}", exit_status));
                     }
                     None => {
-                        info!("Monitor channel disconnected for session: {}", session_id);
-                        break;
+                        return Err("Monitor channel disconnected".to_string());
                     }
                     _ => {}
                 }
@@ -339,3 +1696,184 @@ done
         }
     }
 }
+
+fn record_history(session_id: &str, stats: &ServerStats, history_size: usize) {
+    let mut map = MONITOR_HISTORY.lock().unwrap();
+    let history = map.entry(session_id.to_string()).or_default();
+    history.push_back(MonitorSample::from(stats));
+    while history.len() > history_size {
+        history.pop_front();
+    }
+}
+
+/// 按新的 history_size 立即丢弃多余的旧样本
+fn trim_history(session_id: &str, history_size: usize) {
+    if let Some(history) = MONITOR_HISTORY.lock().unwrap().get_mut(session_id) {
+        let excess = history.len().saturating_sub(history_size);
+        history.drain(..excess);
+    }
+}
+
+pub fn clear_history(session_id: &str) {
+    MONITOR_HISTORY.lock().unwrap().remove(session_id);
+}
+
+/// 取最近 minutes 分钟内的样本（为空时取全部），按时间先后排列
+fn history_since(session_id: &str, minutes: Option<u64>) -> Vec<MonitorSample> {
+    let since = minutes.map(|minutes| now_secs().saturating_sub(minutes * 60));
+    MONITOR_HISTORY
+        .lock()
+        .unwrap()
+        .get(session_id)
+        .map(|history| {
+            history
+                .iter()
+                .filter(|sample| since.is_none_or(|since| sample.timestamp >= since))
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 查询会话最近 minutes 分钟的监控样本
+#[tauri::command]
+pub async fn ssh_monitor_history(session_id: String, minutes: Option<u64>) -> Result<Vec<MonitorSample>, String> {
+    Ok(history_since(&session_id, minutes))
+}
+
+/// 将最近 minutes 分钟的监控样本导出到本地文件，format 为 "csv" 或 "json"，返回样本数
+#[tauri::command]
+pub async fn ssh_monitor_export(
+    session_id: String,
+    minutes: Option<u64>,
+    format: String,
+    local_path: String,
+) -> Result<usize, String> {
+    let history = history_since(&session_id, minutes);
+    let content = match format.as_str() {
+        "json" => serde_json::to_string_pretty(&history).map_err(|e| e.to_string())?,
+        "csv" => history_to_csv(&history),
+        _ => return Err(format!("Unsupported export format: {}", format)),
+    };
+    tokio::fs::write(&local_path, content)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(history.len())
+}
+
+/// 历史中保存的精简样本：网络、磁盘按所有接口/设备汇总，文件系统取使用率最高的一个
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonitorSample {
+    pub timestamp: u64,
+    pub cpu_usage: f64,
+    pub load1: f64,
+    pub load5: f64,
+    pub load15: f64,
+    pub uptime_secs: u64,
+    pub mem_total_kb: u64,
+    pub mem_used_kb: u64,
+    pub mem_usage: f64,
+    pub swap_total_kb: u64,
+    pub swap_used_kb: u64,
+    pub net_rx_speed: f64,
+    pub net_tx_speed: f64,
+    pub disk_read_speed: f64,
+    pub disk_write_speed: f64,
+    pub disk_read_iops: f64,
+    pub disk_write_iops: f64,
+    pub max_fs_usage: f64,
+    pub max_fs_mount: String,
+}
+
+impl From<&ServerStats> for MonitorSample {
+    fn from(stats: &ServerStats) -> Self {
+        let fullest = stats.filesystems.iter().max_by(|a, b| a.usage.total_cmp(&b.usage));
+        MonitorSample {
+            timestamp: stats.timestamp,
+            cpu_usage: stats.cpu_usage,
+            load1: stats.load1,
+            load5: stats.load5,
+            load15: stats.load15,
+            uptime_secs: stats.uptime_secs,
+            mem_total_kb: stats.mem_total_kb,
+            mem_used_kb: stats.mem_used_kb,
+            mem_usage: stats.mem_usage,
+            swap_total_kb: stats.swap_total_kb,
+            swap_used_kb: stats.swap_used_kb,
+            net_rx_speed: stats.net_interfaces.iter().map(|i| i.rx_speed).sum(),
+            net_tx_speed: stats.net_interfaces.iter().map(|i| i.tx_speed).sum(),
+            disk_read_speed: stats.disk_io.iter().map(|d| d.read_speed).sum(),
+            disk_write_speed: stats.disk_io.iter().map(|d| d.write_speed).sum(),
+            disk_read_iops: stats.disk_io.iter().map(|d| d.read_iops).sum(),
+            disk_write_iops: stats.disk_io.iter().map(|d| d.write_iops).sum(),
+            max_fs_usage: fullest.map(|fs| fs.usage).unwrap_or(0.0),
+            max_fs_mount: fullest.map(|fs| fs.mount.clone()).unwrap_or_default(),
+        }
+    }
+}
+
+/// CSV 每行一个样本
+fn history_to_csv(history: &[MonitorSample]) -> String {
+    let mut csv = String::from(
+        "timestamp,cpu_usage,load1,load5,load15,uptime_secs,mem_total_kb,mem_used_kb,mem_usage,\
+         swap_total_kb,swap_used_kb,net_rx_speed,net_tx_speed,disk_read_speed,disk_write_speed,\
+         disk_read_iops,disk_write_iops,max_fs_usage,max_fs_mount\n",
+    );
+    for sample in history {
+        let _ = writeln!(
+            csv,
+            "{},{:.2},{:.2},{:.2},{:.2},{},{},{},{:.2},{},{},{:.0},{:.0},{:.0},{:.0},{:.1},{:.1},{:.2},{}",
+            sample.timestamp,
+            sample.cpu_usage,
+            sample.load1,
+            sample.load5,
+            sample.load15,
+            sample.uptime_secs,
+            sample.mem_total_kb,
+            sample.mem_used_kb,
+            sample.mem_usage,
+            sample.swap_total_kb,
+            sample.swap_used_kb,
+            sample.net_rx_speed,
+            sample.net_tx_speed,
+            sample.disk_read_speed,
+            sample.disk_write_speed,
+            sample.disk_read_iops,
+            sample.disk_write_iops,
+            sample.max_fs_usage,
+            csv_field(&sample.max_fs_mount),
+        );
+    }
+    csv
+}
+
+/// 含逗号、引号或换行时按 CSV 规则加引号
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// 向远端进程发送信号（默认 TERM）
+#[tauri::command]
+pub async fn ssh_monitor_kill(session_id: String, pid: u32, signal: Option<String>) -> Result<(), String> {
+    let signal = signal
+        .as_deref()
+        .map(|s| s.trim_start_matches("SIG").to_uppercase())
+        .unwrap_or_else(|| "TERM".to_string());
+    if !KILL_SIGNALS.contains(&signal.as_str()) {
+        return Err(format!("Unsupported signal: {}", signal));
+    }
+    if pid <= 1 {
+        return Err(format!("Refusing to signal pid {}", pid));
+    }
+    let output = exec_output(&session_id, &format!("kill -s {} {}", signal, pid)).await?;
+    if output.success() {
+        Ok(())
+    } else {
+        Err(output.error_message())
+    }
+}
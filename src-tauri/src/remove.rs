@@ -0,0 +1,305 @@
+use crate::sftp::remote_join;
+use crate::ssh::ssh_get_sftp;
+use once_cell::sync::Lazy;
+use russh_sftp::client::SftpSession;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+
+/// 禁止递归删除的系统目录（用户主目录另行判断）
+/// 比较前会在远端规范化，合并 /usr 的系统上 /lib、/bin 等实际指向 /usr 下的目录
+const PROTECTED_PATHS: &[&str] = &[
+    "/", "/bin", "/boot", "/boot/efi", "/dev", "/etc", "/home", "/lib", "/lib32", "/lib64",
+    "/libx32", "/media", "/mnt", "/opt", "/proc", "/root", "/run", "/sbin", "/snap", "/srv",
+    "/sys", "/tmp", "/usr", "/usr/bin", "/usr/include", "/usr/lib", "/usr/lib32", "/usr/lib64",
+    "/usr/libexec", "/usr/libx32", "/usr/local", "/usr/local/bin", "/usr/sbin", "/usr/share",
+    "/usr/src", "/var", "/var/cache", "/var/lib", "/var/log", "/var/spool", "/var/tmp",
+    // macOS
+    "/Applications", "/Library", "/System", "/Users", "/private", "/private/etc", "/private/var",
+];
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 删除目录选项；省略时只删除空目录
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RemoveDirOptions {
+    /// 递归删除目录及其内容
+    pub recursive: bool,
+    /// 只返回将要删除的条目，不做任何修改（仅递归删除时有效）
+    pub dry_run: bool,
+    /// 用于 ssh_sftp_remove_cancel 取消递归删除
+    pub task_id: Option<String>,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoveEntry {
+    pub path: String,
+    pub is_dir: bool,
+    pub size: u64,
+}
+
+/// 删除结果；dry_run 时 entries 为将要删除的条目（按删除顺序，子项在前）
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoveSummary {
+    pub files: u32,
+    pub dirs: u32,
+    pub total_size: u64,
+    pub failed: u32,
+    pub entries: Vec<RemoveEntry>,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase",
+    tag = "event",
+    content = "data"
+)]
+pub enum RemoveEvent {
+    /// 扫描待删除条目中
+    Scanning {
+        scanned: u32,
+    },
+    /// 删除进度（节流发送）
+    Process {
+        val: u32,
+        removed: u32,
+        total: u32,
+        current: String,
+    },
+    /// 单个条目删除失败，继续删除其余条目
+    Error {
+        path: String,
+        message: String,
+    },
+    Finished {
+        removed: u32,
+        failed: u32,
+        elapsed_ms: u64,
+    },
+    Cancelled,
+}
+
+static REMOVE_TASKS: Lazy<Mutex<HashMap<String, CancellationToken>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 删除目录；默认只删除空目录，options.recursive 为 true 时递归删除
+/// dry_run 为 true 时只返回将要删除的条目，不做任何修改；根目录、系统目录及用户主目录不允许递归删除
+/// 符号链接只删除链接本身，不进入其指向的目录
+#[tauri::command]
+pub async fn ssh_sftp_remove_dir(
+    session_id: &str,
+    dir: &str,
+    options: Option<RemoveDirOptions>,
+    on_event: Option<tauri::ipc::Channel<RemoveEvent>>,
+) -> Result<RemoveSummary, String> {
+    let options = options.unwrap_or_default();
+    let sftp = ssh_get_sftp(session_id).await?;
+    if !options.recursive {
+        sftp.remove_dir(dir).await.map_err(|e| e.to_string())?;
+        return Ok(RemoveSummary { files: 0, dirs: 1, total_size: 0, failed: 0, entries: Vec::new() });
+    }
+    let on_event = on_event.as_ref();
+    let meta = sftp.symlink_metadata(dir).await.map_err(|e| e.to_string())?;
+    let is_link = meta.file_type().is_symlink();
+
+    // 规范化路径（消除 ..、. 及路径中的符号链接）后再做保护判断；符号链接本身不解析，只规范化其所在目录
+    let target = if is_link {
+        let trimmed = dir.trim_end_matches('/');
+        match trimmed.rsplit_once('/') {
+            Some((parent, name)) => {
+                let parent = sftp
+                    .canonicalize(if parent.is_empty() { "/" } else { parent })
+                    .await
+                    .map_err(|e| e.to_string())?;
+                remote_join(&parent, name)
+            }
+            None => remote_join(&sftp.canonicalize(".").await.map_err(|e| e.to_string())?, trimmed),
+        }
+    } else {
+        sftp.canonicalize(dir).await.map_err(|e| e.to_string())?
+    };
+    check_protected(&sftp, &target).await?;
+
+    let token = CancellationToken::new();
+    if let Some(task_id) = &options.task_id {
+        REMOVE_TASKS.lock().await.insert(task_id.clone(), token.clone());
+    }
+    let started = Instant::now();
+
+    let result = async {
+        let entries = if meta.file_type().is_dir() {
+            collect_entries(&sftp, &target, &token, on_event).await?
+        } else {
+            vec![RemoveEntry { path: target.clone(), is_dir: false, size: meta.size.unwrap_or(0) }]
+        };
+        let mut summary = RemoveSummary {
+            files: entries.iter().filter(|e| !e.is_dir).count() as u32,
+            dirs: entries.iter().filter(|e| e.is_dir).count() as u32,
+            total_size: entries.iter().map(|e| e.size).sum(),
+            failed: 0,
+            entries: Vec::new(),
+        };
+        if options.dry_run {
+            summary.entries = entries;
+            return Ok(summary);
+        }
+        summary.failed = remove_entries(&sftp, &entries, &token, on_event).await?;
+        emit(on_event, RemoveEvent::Finished {
+            removed: entries.len() as u32 - summary.failed,
+            failed: summary.failed,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        });
+        Ok(summary)
+    }
+    .await;
+
+    if let Some(task_id) = &options.task_id {
+        let mut map = REMOVE_TASKS.lock().await;
+        if !token.is_cancelled() {
+            map.remove(task_id);
+        }
+    }
+    if result.is_err() && token.is_cancelled() {
+        emit(on_event, RemoveEvent::Cancelled);
+    }
+    result
+}
+
+#[tauri::command]
+pub async fn ssh_sftp_remove_cancel(task_id: String) -> Result<(), String> {
+    let mut map = REMOVE_TASKS.lock().await;
+
+    if let Some(token) = map.remove(&task_id) {
+        token.cancel();
+        Ok(())
+    } else {
+        Err("任务不存在".to_string())
+    }
+}
+
+fn emit(on_event: Option<&tauri::ipc::Channel<RemoveEvent>>, event: RemoveEvent) {
+    if let Some(on_event) = on_event {
+        let _ = on_event.send(event);
+    }
+}
+
+async fn check_protected(sftp: &Arc<SftpSession>, path: &str) -> Result<(), String> {
+    let normalized = if path.len() > 1 { path.trim_end_matches('/') } else { path };
+    if PROTECTED_PATHS.contains(&normalized) {
+        return Err(format!("Refusing to delete protected path: {}", normalized));
+    }
+    // 受保护目录本身可能是符号链接（如 /lib -> usr/lib），按其规范化后的路径再比较一次
+    let mut resolved = JoinSet::new();
+    for protected in PROTECTED_PATHS {
+        let sftp = sftp.clone();
+        resolved.spawn(async move { sftp.canonicalize(*protected).await });
+    }
+    while let Some(result) = resolved.join_next().await {
+        if let Ok(Ok(real)) = result {
+            if real == normalized {
+                return Err(format!("Refusing to delete protected path: {}", normalized));
+            }
+        }
+    }
+    // sftp 会话的初始目录即用户主目录
+    if let Ok(home) = sftp.canonicalize(".").await {
+        let home = if home.len() > 1 { home.trim_end_matches('/') } else { home.as_str() };
+        if normalized == home {
+            return Err(format!("Refusing to delete home directory: {}", normalized));
+        }
+    }
+    Ok(())
+}
+
+/// 后序遍历，返回删除顺序（子项在前，目录在后）
+async fn collect_entries(
+    sftp: &SftpSession,
+    root: &str,
+    token: &CancellationToken,
+    on_event: Option<&tauri::ipc::Channel<RemoveEvent>>,
+) -> Result<Vec<RemoveEntry>, String> {
+    let mut entries = Vec::new();
+    // (路径, 子项是否已展开)
+    let mut stack = vec![(root.to_string(), false)];
+    let mut last_emit = Instant::now();
+
+    while let Some((dir, expanded)) = stack.pop() {
+        if expanded {
+            entries.push(RemoveEntry { path: dir, is_dir: true, size: 0 });
+            continue;
+        }
+        let children = tokio::select! {
+            _ = token.cancelled() => return Err("删除已取消".to_string()),
+            res = sftp.read_dir(dir.as_str()) => res.map_err(|e| format!("{}: {}", dir, e))?,
+        };
+        stack.push((dir.clone(), true));
+        for child in children {
+            let path = remote_join(&dir, &child.file_name());
+            let metadata = child.metadata();
+            if metadata.file_type().is_dir() {
+                stack.push((path, false));
+            } else {
+                entries.push(RemoveEntry { path, is_dir: false, size: metadata.size.unwrap_or(0) });
+            }
+        }
+        if last_emit.elapsed() >= PROGRESS_INTERVAL {
+            last_emit = Instant::now();
+            emit(on_event, RemoveEvent::Scanning { scanned: entries.len() as u32 });
+        }
+    }
+    Ok(entries)
+}
+
+/// 按顺序删除，返回失败数
+async fn remove_entries(
+    sftp: &SftpSession,
+    entries: &[RemoveEntry],
+    token: &CancellationToken,
+    on_event: Option<&tauri::ipc::Channel<RemoveEvent>>,
+) -> Result<u32, String> {
+    let total = entries.len() as u32;
+    let mut removed: u32 = 0;
+    let mut failed: u32 = 0;
+    let mut last_emit = Instant::now();
+
+    for entry in entries {
+        let result = tokio::select! {
+            _ = token.cancelled() => return Err("删除已取消".to_string()),
+            res = async {
+                if entry.is_dir {
+                    sftp.remove_dir(entry.path.as_str()).await
+                } else {
+                    sftp.remove_file(entry.path.as_str()).await
+                }
+            } => res,
+        };
+        match result {
+            Ok(_) => removed += 1,
+            Err(e) => {
+                failed += 1;
+                emit(on_event, RemoveEvent::Error {
+                    path: entry.path.clone(),
+                    message: e.to_string(),
+                });
+            }
+        }
+        if last_emit.elapsed() >= PROGRESS_INTERVAL || removed + failed == total {
+            last_emit = Instant::now();
+            emit(on_event, RemoveEvent::Process {
+                val: ((removed + failed) as f64 / total.max(1) as f64 * 100.0) as u32,
+                removed,
+                total,
+                current: entry.path.clone(),
+            });
+        }
+    }
+    Ok(failed)
+}
@@ -0,0 +1,753 @@
+use crate::exec::{exec_channel, shell_quote};
+use crate::sftp::{SftpDownloadEvent, SftpUploadEvent};
+use crate::transfer::{finish, percent, register_task, transfer_speed, TransferEvent, TransferTasks};
+use log::warn;
+use once_cell::sync::Lazy;
+use russh::client::Msg;
+use russh::Channel;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::time::Instant;
+use tokio::io::{AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+const SCP_BUFFER_SIZE: usize = 32 * 1024;
+const SCP_CANCELLED: &str = "传输已取消";
+/// 保留的 stderr 上限，用于失败时的错误信息
+const SCP_STDERR_LIMIT: usize = 4096;
+
+#[derive(Clone, serde::Serialize)]
+#[serde(
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase",
+    tag = "event",
+    content = "data"
+)]
+pub enum ScpTransferEvent {
+    /// 开始传输某个文件
+    File {
+        path: String,
+        size: u64,
+    },
+    /// 上传时 val 为整体进度；下载时总大小未知，val 为当前文件进度
+    Process {
+        val: u32,
+        transferred: u64,
+        speed: f64,
+    },
+    Finished {
+        transferred: u64,
+        elapsed_ms: u64,
+    },
+    Cancelled,
+}
+
+/// SCP 协议运行在 exec 通道上，服务端为 `scp -f`（发送）或 `scp -t`（接收）
+/// 直接读取通道消息而非转换为字节流，以便取得 stderr 和退出码
+struct ScpStream {
+    channel: Channel<Msg>,
+    writer: Pin<Box<dyn AsyncWrite + Send>>,
+    buffer: Vec<u8>,
+    pos: usize,
+    /// 对端已发送 EOF 或关闭通道
+    eof: bool,
+    exit_status: Option<u32>,
+    stderr: Vec<u8>,
+    /// 对端发送的警告（0x01），如某个文件无法读取或写入
+    warnings: Vec<String>,
+}
+
+impl ScpStream {
+    fn new(channel: Channel<Msg>) -> Self {
+        let writer = Box::pin(channel.make_writer());
+        ScpStream {
+            channel,
+            writer,
+            buffer: Vec::new(),
+            pos: 0,
+            eof: false,
+            exit_status: None,
+            stderr: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// 读取下一条数据消息，返回 false 表示对端不会再发送数据
+    async fn fill(&mut self) -> bool {
+        while !self.eof {
+            match self.channel.wait().await {
+                Some(russh::ChannelMsg::Data { ref data }) => {
+                    self.buffer.drain(..self.pos);
+                    self.pos = 0;
+                    self.buffer.extend_from_slice(data);
+                    return true;
+                }
+                Some(russh::ChannelMsg::ExtendedData { ref data, ext: 1 }) => {
+                    let room = SCP_STDERR_LIMIT.saturating_sub(self.stderr.len());
+                    self.stderr.extend_from_slice(&data[..data.len().min(room)]);
+                }
+                Some(russh::ChannelMsg::ExitStatus { exit_status }) => {
+                    self.exit_status = Some(exit_status);
+                }
+                Some(russh::ChannelMsg::Eof) | Some(russh::ChannelMsg::Close) | None => self.eof = true,
+                _ => {}
+            }
+        }
+        false
+    }
+
+    async fn read_u8(&mut self) -> Result<u8, String> {
+        if self.pos == self.buffer.len() && !self.fill().await {
+            return Err("scp: 连接意外中断".to_string());
+        }
+        self.pos += 1;
+        Ok(self.buffer[self.pos - 1])
+    }
+
+    /// 读取一行（含 \n）追加到 line，返回读取的字节数，为 0 表示对端已结束
+    async fn read_line(&mut self, line: &mut Vec<u8>) -> usize {
+        let mut total = 0;
+        loop {
+            if self.pos == self.buffer.len() && !self.fill().await {
+                return total;
+            }
+            let available = &self.buffer[self.pos..];
+            match available.iter().position(|b| *b == b'\n') {
+                Some(i) => {
+                    line.extend_from_slice(&available[..=i]);
+                    self.pos += i + 1;
+                    return total + i + 1;
+                }
+                None => {
+                    line.extend_from_slice(available);
+                    total += available.len();
+                    self.pos = self.buffer.len();
+                }
+            }
+        }
+    }
+
+    async fn read(&mut self, buf: &mut [u8]) -> usize {
+        if self.pos == self.buffer.len() && !self.fill().await {
+            return 0;
+        }
+        let n = buf.len().min(self.buffer.len() - self.pos);
+        buf[..n].copy_from_slice(&self.buffer[self.pos..self.pos + n]);
+        self.pos += n;
+        n
+    }
+
+    async fn write_all(&mut self, data: &[u8]) -> Result<(), String> {
+        self.writer.write_all(data).await.map_err(|e| e.to_string())?;
+        self.writer.flush().await.map_err(|e| e.to_string())
+    }
+
+    /// 发送 EOF 并等待远端 scp 退出，退出码非 0 或传输中有警告时返回错误
+    async fn finish(&mut self) -> Result<(), String> {
+        let _ = self.channel.eof().await;
+        loop {
+            match self.channel.wait().await {
+                Some(russh::ChannelMsg::ExtendedData { ref data, ext: 1 }) => {
+                    let room = SCP_STDERR_LIMIT.saturating_sub(self.stderr.len());
+                    self.stderr.extend_from_slice(&data[..data.len().min(room)]);
+                }
+                Some(russh::ChannelMsg::ExitStatus { exit_status }) => {
+                    self.exit_status = Some(exit_status);
+                }
+                Some(russh::ChannelMsg::Close) | None => break,
+                _ => {}
+            }
+        }
+        if !self.warnings.is_empty() {
+            return Err(format!("scp: {}", self.warnings.join("; ")));
+        }
+        match self.exit_status {
+            Some(0) => Ok(()),
+            status => {
+                let stderr = String::from_utf8_lossy(&self.stderr).trim().to_string();
+                if !stderr.is_empty() {
+                    Err(format!("scp: {}", stderr))
+                } else if let Some(status) = status {
+                    Err(format!("scp exited with status {}", status))
+                } else {
+                    Err("scp terminated without exit status".to_string())
+                }
+            }
+        }
+    }
+
+    async fn close(&self) {
+        let _ = self.channel.close().await;
+    }
+}
+
+static SCP_TASKS: Lazy<TransferTasks> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+impl TransferEvent for ScpTransferEvent {
+    fn finished(transferred: u64, elapsed_ms: u64) -> Self {
+        Self::Finished { transferred, elapsed_ms }
+    }
+
+    fn cancelled() -> Self {
+        Self::Cancelled
+    }
+}
+
+/// 单次进度回调的内容
+pub struct ScpProgress<'a> {
+    /// 当前文件（本地路径或远端名称）
+    pub path: &'a str,
+    pub file_size: u64,
+    /// 当前文件已传输字节，为 0 表示文件刚开始
+    pub file_done: u64,
+    /// 本次任务累计传输字节
+    pub transferred: u64,
+}
+
+type OnProgress<'a> = dyn FnMut(ScpProgress) + Send + 'a;
+
+/// 接收数据的落地方式
+pub enum ScpSink<'a> {
+    /// 写入本地路径：已存在的目录则写入其下，否则作为目标文件/目录名
+    Local(PathBuf),
+    /// 单文件数据块直接发送给前端
+    Channel(&'a tauri::ipc::Channel<SftpDownloadEvent>),
+}
+
+/// 从远端下载文件或目录（`scp -f`）
+#[tauri::command]
+pub async fn ssh_scp_download(
+    task_id: &str,
+    session_id: &str,
+    remote_path: &str,
+    local_path: &str,
+    recursive: bool,
+    on_event: tauri::ipc::Channel<ScpTransferEvent>,
+) -> Result<String, String> {
+    let token = register_task(&SCP_TASKS, task_id).await;
+    let started = Instant::now();
+    let mut last_progress = u32::MAX;
+
+    let result = scp_recv(
+        session_id,
+        remote_path,
+        recursive,
+        ScpSink::Local(PathBuf::from(local_path)),
+        &token,
+        &mut |p: ScpProgress| {
+            if p.file_done == 0 {
+                let _ = on_event.send(ScpTransferEvent::File {
+                    path: p.path.to_string(),
+                    size: p.file_size,
+                });
+            }
+            let val = percent(p.file_done, p.file_size);
+            if val != last_progress {
+                last_progress = val;
+                let _ = on_event.send(ScpTransferEvent::Process {
+                    val,
+                    transferred: p.transferred,
+                    speed: transfer_speed(p.transferred, started),
+                });
+            }
+        },
+    )
+    .await;
+
+    SCP_TASKS.lock().await.remove(task_id);
+    finish(result, started, SCP_CANCELLED, &on_event)
+}
+
+/// 上传本地文件或目录到远端（`scp -t`）
+#[tauri::command]
+pub async fn ssh_scp_upload(
+    task_id: &str,
+    session_id: &str,
+    local_path: &str,
+    remote_path: &str,
+    recursive: bool,
+    on_event: tauri::ipc::Channel<ScpTransferEvent>,
+) -> Result<String, String> {
+    let started = Instant::now();
+    let local = PathBuf::from(local_path);
+    let total = {
+        let local = local.clone();
+        tokio::task::spawn_blocking(move || local_total_size(&local))
+            .await
+            .map_err(|e| e.to_string())?
+    };
+    // 统计完成后再登记任务，之后的每条路径都会移除该任务
+    let token = register_task(&SCP_TASKS, task_id).await;
+    let mut last_progress = u32::MAX;
+
+    let result = scp_send(
+        session_id,
+        &local,
+        remote_path,
+        recursive,
+        &token,
+        &mut |p: ScpProgress| {
+            if p.file_done == 0 {
+                let _ = on_event.send(ScpTransferEvent::File {
+                    path: p.path.to_string(),
+                    size: p.file_size,
+                });
+            }
+            let val = percent(p.transferred, total);
+            if val != last_progress {
+                last_progress = val;
+                let _ = on_event.send(ScpTransferEvent::Process {
+                    val,
+                    transferred: p.transferred,
+                    speed: transfer_speed(p.transferred, started),
+                });
+            }
+        },
+    )
+    .await;
+
+    SCP_TASKS.lock().await.remove(task_id);
+    finish(result, started, SCP_CANCELLED, &on_event)
+}
+
+#[tauri::command]
+pub async fn ssh_scp_cancel(task_id: String) -> Result<(), String> {
+    let mut map = SCP_TASKS.lock().await;
+
+    if let Some(token) = map.remove(&task_id) {
+        token.cancel();
+        Ok(())
+    } else {
+        Err("任务不存在".to_string())
+    }
+}
+
+/// sftp 不可用时 `ssh_sftp_read` 的回退实现，事件格式与 sftp 下载一致
+pub async fn scp_read_to_channel(
+    session_id: &str,
+    file_path: &str,
+    token: &CancellationToken,
+    on_down_event: &tauri::ipc::Channel<SftpDownloadEvent>,
+) -> Result<(), String> {
+    let mut last_progress = 0;
+    let result = scp_recv(
+        session_id,
+        file_path,
+        false,
+        ScpSink::Channel(on_down_event),
+        token,
+        &mut |p: ScpProgress| {
+            let val = percent(p.file_done, p.file_size);
+            if val != last_progress {
+                last_progress = val;
+                let _ = on_down_event.send(SftpDownloadEvent::Process { val });
+            }
+        },
+    )
+    .await;
+
+    match result {
+        Ok(_) => {
+            let _ = on_down_event.send(SftpDownloadEvent::Process { val: 100 });
+            let _ = on_down_event.send(SftpDownloadEvent::Finished);
+            Ok(())
+        }
+        Err(e) => {
+            if token.is_cancelled() {
+                let _ = on_down_event.send(SftpDownloadEvent::Cancelled);
+                return Err("下载已取消".to_string());
+            }
+            Err(e)
+        }
+    }
+}
+
+/// sftp 不可用时 `ssh_sftp_upload` 的回退实现，事件格式与 sftp 上传一致
+pub async fn scp_upload_file(
+    session_id: &str,
+    local_path: &str,
+    remote_path: &str,
+    token: &CancellationToken,
+    on_up_event: &tauri::ipc::Channel<SftpUploadEvent>,
+) -> Result<(), String> {
+    let started = Instant::now();
+    let mut last_progress = 0;
+    let mut total = 0;
+    let result = scp_send(
+        session_id,
+        Path::new(local_path),
+        remote_path,
+        false,
+        token,
+        &mut |p: ScpProgress| {
+            total = p.file_size;
+            let val = percent(p.file_done, p.file_size);
+            if val != last_progress {
+                last_progress = val;
+                let _ = on_up_event.send(SftpUploadEvent::Process {
+                    val,
+                    transferred: p.transferred,
+                    total: p.file_size,
+                    speed: transfer_speed(p.transferred, started),
+                });
+            }
+        },
+    )
+    .await;
+
+    match result {
+        Ok(transferred) => {
+            let _ = on_up_event.send(SftpUploadEvent::Process {
+                val: 100,
+                transferred,
+                total,
+                speed: transfer_speed(transferred, started),
+            });
+            let _ = on_up_event.send(SftpUploadEvent::Finished {
+                total: transferred,
+                elapsed_ms: started.elapsed().as_millis() as u64,
+            });
+            Ok(())
+        }
+        Err(e) => {
+            if token.is_cancelled() {
+                let _ = on_up_event.send(SftpUploadEvent::Cancelled);
+                return Err("上传已取消".to_string());
+            }
+            Err(e)
+        }
+    }
+}
+
+/// 执行 `scp -f` 接收远端文件，返回累计接收字节数
+pub async fn scp_recv(
+    session_id: &str,
+    remote_path: &str,
+    recursive: bool,
+    sink: ScpSink<'_>,
+    token: &CancellationToken,
+    on_progress: &mut OnProgress<'_>,
+) -> Result<u64, String> {
+    let flags = if recursive { "-r -f" } else { "-f" };
+    let command = format!("scp {} {}", flags, shell_quote(remote_path));
+    let channel = exec_channel(session_id, &command).await?;
+    let mut stream = ScpStream::new(channel);
+
+    let result = tokio::select! {
+        _ = token.cancelled() => Err(SCP_CANCELLED.to_string()),
+        res = async {
+            let transferred = recv_loop(&mut stream, &sink, on_progress).await?;
+            stream.finish().await?;
+            Ok(transferred)
+        } => res,
+    };
+    if result.is_err() {
+        stream.close().await;
+    }
+    result
+}
+
+/// 执行 `scp -t` 发送本地文件或目录，返回累计发送字节数
+pub async fn scp_send(
+    session_id: &str,
+    local_path: &Path,
+    remote_path: &str,
+    recursive: bool,
+    token: &CancellationToken,
+    on_progress: &mut OnProgress<'_>,
+) -> Result<u64, String> {
+    let meta = tokio::fs::metadata(local_path)
+        .await
+        .map_err(|e| e.to_string())?;
+    if meta.is_dir() && !recursive {
+        return Err(format!("{} 是目录，需要递归传输", local_path.display()));
+    }
+    let flags = if meta.is_dir() { "-r -t" } else { "-t" };
+    let command = format!("scp {} {}", flags, shell_quote(remote_path));
+    let channel = exec_channel(session_id, &command).await?;
+    let mut stream = ScpStream::new(channel);
+
+    let result = tokio::select! {
+        _ = token.cancelled() => Err(SCP_CANCELLED.to_string()),
+        res = async {
+            // 服务端就绪后先回复一个 \0
+            read_ack(&mut stream).await?;
+            let mut transferred = 0u64;
+            send_entry(&mut stream, local_path, meta, &mut transferred, on_progress).await?;
+            stream.finish().await?;
+            Ok(transferred)
+        } => res,
+    };
+    if result.is_err() {
+        stream.close().await;
+    }
+    result
+}
+
+async fn recv_loop(
+    stream: &mut ScpStream,
+    sink: &ScpSink<'_>,
+    on_progress: &mut OnProgress<'_>,
+) -> Result<u64, String> {
+    // 当前所在的本地目录栈（对应 D/E 指令）
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    let mut transferred = 0u64;
+
+    send_ack(stream).await?;
+    loop {
+        let mut line = Vec::new();
+        if stream.read_line(&mut line).await == 0 {
+            break;
+        }
+        let kind = line[0];
+        let text = String::from_utf8_lossy(&line[1..]).trim_end().to_string();
+
+        match kind {
+            // 0x01 为警告（如某个文件无法读取），对端会继续发送后续条目，结束后报错
+            0x01 => {
+                warn!("scp: {}", text);
+                stream.warnings.push(text);
+                continue;
+            }
+            0x02 => return Err(format!("scp: {}", text)),
+            // 时间戳（-p），忽略
+            b'T' => {}
+            b'E' => {
+                dirs.pop();
+            }
+            b'C' | b'D' => {
+                let (mode, size, name) = parse_header(&text)?;
+                if name.contains('/') || name == "." || name == ".." {
+                    return Err(format!("scp: 非法文件名 {}", name));
+                }
+
+                match sink {
+                    ScpSink::Local(root) => {
+                        let target = match dirs.last() {
+                            Some(dir) => dir.join(&name),
+                            None if root.is_dir() => root.join(&name),
+                            None => root.clone(),
+                        };
+                        if kind == b'D' {
+                            tokio::fs::create_dir_all(&target)
+                                .await
+                                .map_err(|e| e.to_string())?;
+                            set_local_mode(&target, mode);
+                            dirs.push(target);
+                        } else {
+                            let mut file = tokio::fs::File::create(&target)
+                                .await
+                                .map_err(|e| e.to_string())?;
+                            let path = target.to_string_lossy().to_string();
+                            send_ack(stream).await?;
+                            let mut buffer = vec![0u8; SCP_BUFFER_SIZE];
+                            on_progress(ScpProgress { path: &path, file_size: size, file_done: 0, transferred });
+                            let mut done = 0u64;
+                            while done < size {
+                                let want = (size - done).min(buffer.len() as u64) as usize;
+                                let n = stream.read(&mut buffer[..want]).await;
+                                if n == 0 {
+                                    return Err("scp: 连接意外中断".to_string());
+                                }
+                                file.write_all(&buffer[..n]).await.map_err(|e| e.to_string())?;
+                                done += n as u64;
+                                transferred += n as u64;
+                                on_progress(ScpProgress { path: &path, file_size: size, file_done: done, transferred });
+                            }
+                            file.flush().await.map_err(|e| e.to_string())?;
+                            set_local_mode(&target, mode);
+                            read_ack(stream).await?;
+                        }
+                    }
+                    ScpSink::Channel(on_down_event) => {
+                        if kind == b'D' {
+                            return Err("scp: 不支持以数据流方式读取目录".to_string());
+                        }
+                        send_ack(stream).await?;
+                        let mut buffer = vec![0u8; SCP_BUFFER_SIZE];
+                        on_progress(ScpProgress { path: &name, file_size: size, file_done: 0, transferred });
+                        let mut done = 0u64;
+                        while done < size {
+                            let want = (size - done).min(buffer.len() as u64) as usize;
+                            let n = stream.read(&mut buffer[..want]).await;
+                            if n == 0 {
+                                return Err("scp: 连接意外中断".to_string());
+                            }
+                            let _ = on_down_event.send(SftpDownloadEvent::Chunk {
+                                data: buffer[..n].to_vec(),
+                            });
+                            done += n as u64;
+                            transferred += n as u64;
+                            on_progress(ScpProgress { path: &name, file_size: size, file_done: done, transferred });
+                        }
+                        read_ack(stream).await?;
+                    }
+                }
+            }
+            _ => return Err(format!("scp: 协议错误 {}", String::from_utf8_lossy(&line).trim_end())),
+        }
+        send_ack(stream).await?;
+    }
+    Ok(transferred)
+}
+
+fn send_entry<'a>(
+    stream: &'a mut ScpStream,
+    path: &'a Path,
+    meta: std::fs::Metadata,
+    transferred: &'a mut u64,
+    on_progress: &'a mut OnProgress<'_>,
+) -> Pin<Box<dyn Future<Output = Result<(), String>> + Send + 'a>> {
+    Box::pin(async move {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .ok_or_else(|| format!("无效的路径 {}", path.display()))?;
+
+        if meta.is_dir() {
+            let header = format!("D{:04o} 0 {}\n", local_mode(&meta, 0o755), name);
+            stream.write_all(header.as_bytes()).await?;
+            // 对端无法创建该目录时跳过
+            if !read_ack(stream).await? {
+                return Ok(());
+            }
+
+            let mut entries = tokio::fs::read_dir(path).await.map_err(|e| e.to_string())?;
+            while let Some(entry) = entries.next_entry().await.map_err(|e| e.to_string())? {
+                let path = entry.path();
+                let Some(meta) = child_metadata(&path).await else {
+                    continue;
+                };
+                send_entry(stream, &path, meta, transferred, on_progress).await?;
+            }
+
+            stream.write_all(b"E\n").await?;
+            read_ack(stream).await.map(|_| ())
+        } else {
+            let size = meta.len();
+            let display = path.to_string_lossy().to_string();
+            let mut file = tokio::fs::File::open(path).await.map_err(|e| e.to_string())?;
+            let header = format!("C{:04o} {} {}\n", local_mode(&meta, 0o644), size, name);
+            stream.write_all(header.as_bytes()).await?;
+            // 对端无法写入该文件时跳过，不发送数据
+            if !read_ack(stream).await? {
+                return Ok(());
+            }
+
+            on_progress(ScpProgress { path: &display, file_size: size, file_done: 0, transferred: *transferred });
+            let mut buffer = vec![0u8; SCP_BUFFER_SIZE];
+            let mut done = 0u64;
+            while done < size {
+                let want = (size - done).min(buffer.len() as u64) as usize;
+                let n = file.read(&mut buffer[..want]).await.map_err(|e| e.to_string())?;
+                if n == 0 {
+                    return Err(format!("{} 在传输过程中被截断", path.display()));
+                }
+                stream.write_all(&buffer[..n]).await?;
+                done += n as u64;
+                *transferred += n as u64;
+                on_progress(ScpProgress { path: &display, file_size: size, file_done: done, transferred: *transferred });
+            }
+            send_ack(stream).await?;
+            read_ack(stream).await.map(|_| ())
+        }
+    })
+}
+
+/// 目录下条目的元数据：指向文件的符号链接按目标发送，指向目录的符号链接跳过，避免链接成环时无限递归
+async fn child_metadata(path: &Path) -> Option<std::fs::Metadata> {
+    let meta = tokio::fs::symlink_metadata(path).await.ok()?;
+    if !meta.file_type().is_symlink() {
+        return Some(meta);
+    }
+    match tokio::fs::metadata(path).await {
+        Ok(target) if !target.is_dir() => Some(target),
+        _ => {
+            warn!("scp: 跳过符号链接 {}", path.display());
+            None
+        }
+    }
+}
+
+/// 解析 `C0644 1234 name` / `D0755 0 name` 的指令参数（已去掉首字母）
+fn parse_header(text: &str) -> Result<(u32, u64, String), String> {
+    let mut parts = text.splitn(3, ' ');
+    let mode = parts
+        .next()
+        .and_then(|m| u32::from_str_radix(m, 8).ok())
+        .ok_or_else(|| format!("scp: 无效的权限 {}", text))?;
+    let size = parts
+        .next()
+        .and_then(|s| s.parse::<u64>().ok())
+        .ok_or_else(|| format!("scp: 无效的大小 {}", text))?;
+    let name = parts
+        .next()
+        .filter(|n| !n.is_empty())
+        .ok_or_else(|| format!("scp: 缺少文件名 {}", text))?;
+    Ok((mode, size, name.to_string()))
+}
+
+async fn send_ack(stream: &mut ScpStream) -> Result<(), String> {
+    stream.write_all(b"\0").await
+}
+
+/// 读取对端应答：0 成功，1 为警告（记录后返回 false，由调用方跳过当前条目，传输结束后报错），2 为致命错误
+/// 1/2 后跟一行信息
+async fn read_ack(stream: &mut ScpStream) -> Result<bool, String> {
+    let code = stream.read_u8().await?;
+    if code == 0 {
+        return Ok(true);
+    }
+    let mut message = Vec::new();
+    stream.read_line(&mut message).await;
+    let message = String::from_utf8_lossy(&message).trim_end().to_string();
+    if code == 1 {
+        warn!("scp: {}", message);
+        stream.warnings.push(message);
+        return Ok(false);
+    }
+    Err(format!("scp: {}", message))
+}
+
+/// 统计本地文件或目录的总字节数（与 send_entry 一致，不进入指向目录的符号链接）
+fn local_total_size(path: &Path) -> u64 {
+    match std::fs::metadata(path) {
+        Ok(meta) if meta.is_dir() => std::fs::read_dir(path)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter(|entry| {
+                        let is_link = entry.file_type().is_ok_and(|t| t.is_symlink());
+                        !(is_link && entry.path().is_dir())
+                    })
+                    .map(|entry| local_total_size(&entry.path()))
+                    .sum()
+            })
+            .unwrap_or(0),
+        Ok(meta) => meta.len(),
+        Err(_) => 0,
+    }
+}
+
+#[cfg(unix)]
+fn local_mode(meta: &std::fs::Metadata, _default: u32) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    meta.permissions().mode() & 0o7777
+}
+
+#[cfg(not(unix))]
+fn local_mode(_meta: &std::fs::Metadata, default: u32) -> u32 {
+    default
+}
+
+#[cfg(unix)]
+fn set_local_mode(path: &Path, mode: u32) {
+    use std::os::unix::fs::PermissionsExt;
+    let _ = std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode & 0o7777));
+}
+
+#[cfg(not(unix))]
+fn set_local_mode(_path: &Path, _mode: u32) {}
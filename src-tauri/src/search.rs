@@ -0,0 +1,585 @@
+use crate::exec::{exec_channel, shell_quote};
+use crate::sftp::remote_join;
+use crate::ssh::ssh_get_sftp;
+use log::info;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+/// 搜索任务（key: task_id）
+static SEARCH_TASKS: Lazy<Mutex<HashMap<String, CancellationToken>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 文件名搜索条件
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SftpSearchOptions {
+    /// 文件名匹配模式
+    pub pattern: String,
+    /// 匹配方式: "glob" 或 "regex"
+    #[serde(default = "default_match_mode")]
+    pub match_mode: String,
+    /// 是否区分大小写
+    #[serde(default)]
+    pub case_sensitive: bool,
+    /// 最小文件大小（字节）
+    #[serde(default)]
+    pub min_size: Option<u64>,
+    /// 最大文件大小（字节）
+    #[serde(default)]
+    pub max_size: Option<u64>,
+    /// 修改时间下限（Unix 秒）
+    #[serde(default)]
+    pub modified_after: Option<u64>,
+    /// 修改时间上限（Unix 秒）
+    #[serde(default)]
+    pub modified_before: Option<u64>,
+    /// 最大递归深度，起始目录的直接子项为 1
+    #[serde(default)]
+    pub max_depth: Option<u32>,
+    /// 排除的文件/目录名（glob），命中的目录不再进入
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// 目录是否也参与匹配
+    #[serde(default)]
+    pub include_dirs: bool,
+    /// 最多返回的结果数
+    #[serde(default)]
+    pub max_results: Option<u32>,
+}
+
+fn default_match_mode() -> String { "glob".to_string() }
+
+#[derive(Clone, serde::Serialize)]
+#[serde(
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase",
+    tag = "event",
+    content = "data"
+)]
+pub enum SftpSearchEvent {
+    Match {
+        path: String,
+        filename: String,
+        is_dir: bool,
+        size: u64,
+        mtime: u64,
+    },
+    /// 扫描进度（节流发送）
+    Progress {
+        scanned_dirs: u32,
+        current_dir: String,
+    },
+    /// 某个目录无法读取，继续扫描其余目录
+    DirError {
+        path: String,
+        message: String,
+    },
+    Finished {
+        matched: u32,
+        scanned_dirs: u32,
+    },
+    Cancelled,
+}
+
+/// 文件名匹配器
+pub enum NameMatcher {
+    Glob(glob::Pattern, glob::MatchOptions),
+    Regex(regex::Regex),
+}
+
+impl NameMatcher {
+    pub fn new(pattern: &str, match_mode: &str, case_sensitive: bool) -> Result<Self, String> {
+        match match_mode {
+            "regex" => regex::RegexBuilder::new(pattern)
+                .case_insensitive(!case_sensitive)
+                .build()
+                .map(NameMatcher::Regex)
+                .map_err(|e| e.to_string()),
+            "glob" => glob::Pattern::new(pattern)
+                .map(|p| {
+                    NameMatcher::Glob(
+                        p,
+                        glob::MatchOptions {
+                            case_sensitive,
+                            ..Default::default()
+                        },
+                    )
+                })
+                .map_err(|e| e.to_string()),
+            _ => Err(format!("Invalid match_mode: {}", match_mode)),
+        }
+    }
+
+    pub fn is_match(&self, name: &str) -> bool {
+        match self {
+            NameMatcher::Glob(pattern, options) => pattern.matches_with(name, *options),
+            NameMatcher::Regex(regex) => regex.is_match(name),
+        }
+    }
+}
+
+/// 编译排除规则（glob）
+pub fn build_excludes(exclude: &[String]) -> Result<Vec<glob::Pattern>, String> {
+    exclude
+        .iter()
+        .filter(|p| !p.is_empty())
+        .map(|p| glob::Pattern::new(p).map_err(|e| format!("{}: {}", p, e)))
+        .collect()
+}
+
+/// 递归搜索远端目录，匹配结果通过 channel 逐条推送
+#[tauri::command]
+pub async fn ssh_sftp_search(
+    task_id: &str,
+    session_id: &str,
+    dir: &str,
+    options: SftpSearchOptions,
+    on_search_event: tauri::ipc::Channel<SftpSearchEvent>,
+) -> Result<u32, String> {
+    let sftp = ssh_get_sftp(session_id).await?;
+    let matcher = NameMatcher::new(&options.pattern, &options.match_mode, options.case_sensitive)?;
+    let excludes = build_excludes(&options.exclude)?;
+
+    let token = CancellationToken::new();
+    {
+        let mut map = SEARCH_TASKS.lock().await;
+        map.insert(task_id.into(), token.clone());
+    }
+
+    // 广度优先遍历，避免深层目录先占满结果
+    let mut queue: VecDeque<(String, u32)> = VecDeque::new();
+    queue.push_back((dir.to_string(), 0));
+    let mut matched: u32 = 0;
+    let mut scanned_dirs: u32 = 0;
+    let mut last_emit = Instant::now();
+
+    let result = 'walk: loop {
+        let Some((current, depth)) = queue.pop_front() else {
+            break Ok(());
+        };
+
+        let entries = tokio::select! {
+            _ = token.cancelled() => None,
+            res = sftp.read_dir(current.as_str()) => Some(res),
+        };
+        let Some(entries) = entries else {
+            break Err(());
+        };
+        scanned_dirs += 1;
+        let entries = match entries {
+            Ok(entries) => entries,
+            Err(e) => {
+                let _ = on_search_event.send(SftpSearchEvent::DirError {
+                    path: current.clone(),
+                    message: e.to_string(),
+                });
+                continue;
+            }
+        };
+
+        if last_emit.elapsed() >= Duration::from_millis(200) {
+            last_emit = Instant::now();
+            let _ = on_search_event.send(SftpSearchEvent::Progress {
+                scanned_dirs,
+                current_dir: current.clone(),
+            });
+        }
+
+        for entry in entries {
+            let filename = entry.file_name();
+            if excludes.iter().any(|p| p.matches(&filename)) {
+                continue;
+            }
+            let metadata = entry.metadata();
+            let file_type = metadata.file_type();
+            let path = remote_join(&current, &filename);
+
+            // 不跟随符号链接，避免循环
+            if file_type.is_dir() && options.max_depth.is_none_or(|max| depth + 1 < max) {
+                queue.push_back((path.clone(), depth + 1));
+            }
+            if file_type.is_dir() && !options.include_dirs {
+                continue;
+            }
+
+            let size = metadata.size.unwrap_or(0);
+            let mtime = metadata.mtime.unwrap_or(0) as u64;
+            if !matcher.is_match(&filename)
+                || options.min_size.is_some_and(|min| size < min)
+                || options.max_size.is_some_and(|max| size > max)
+                || options.modified_after.is_some_and(|after| mtime < after)
+                || options.modified_before.is_some_and(|before| mtime > before)
+            {
+                continue;
+            }
+
+            let _ = on_search_event.send(SftpSearchEvent::Match {
+                path,
+                filename,
+                is_dir: file_type.is_dir(),
+                size,
+                mtime,
+            });
+            matched += 1;
+            if options.max_results.is_some_and(|max| matched >= max) {
+                break 'walk Ok(());
+            }
+        }
+    };
+
+    SEARCH_TASKS.lock().await.remove(task_id);
+    match result {
+        Ok(()) => {
+            info!("Search finished in {}: {} matches, {} dirs", dir, matched, scanned_dirs);
+            let _ = on_search_event.send(SftpSearchEvent::Finished { matched, scanned_dirs });
+            Ok(matched)
+        }
+        Err(()) => {
+            let _ = on_search_event.send(SftpSearchEvent::Cancelled);
+            Err("搜索已取消".to_string())
+        }
+    }
+}
+
+/// 取消搜索任务（文件名搜索与内容搜索共用）
+#[tauri::command]
+pub async fn ssh_sftp_search_cancel(task_id: String) -> Result<(), String> {
+    let mut map = SEARCH_TASKS.lock().await;
+
+    if let Some(token) = map.remove(&task_id) {
+        token.cancel();
+        Ok(())
+    } else {
+        Err("任务不存在".to_string())
+    }
+}
+
+/// 内容搜索条件
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteGrepOptions {
+    /// 搜索内容
+    pub pattern: String,
+    /// 按普通字符串匹配（否则为扩展正则）
+    #[serde(default)]
+    pub fixed_string: bool,
+    /// 忽略大小写
+    #[serde(default)]
+    pub ignore_case: bool,
+    /// 只搜索匹配这些文件名的文件（glob）
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// 跳过的目录名（glob）
+    #[serde(default)]
+    pub exclude_dirs: Vec<String>,
+    /// 最多返回的匹配行数
+    #[serde(default = "default_grep_max_results")]
+    pub max_results: u32,
+    /// 搜索方式: "auto"（优先 grep，不可用时回退）、"grep" 或 "sftp"
+    #[serde(default = "default_grep_backend")]
+    pub backend: String,
+    /// sftp 回退模式下跳过超过此大小的文件（字节）
+    #[serde(default = "default_grep_max_file_size")]
+    pub max_file_size: u64,
+}
+
+fn default_grep_max_results() -> u32 { 1000 }
+fn default_grep_backend() -> String { "auto".to_string() }
+fn default_grep_max_file_size() -> u64 { 10 * 1024 * 1024 }
+
+/// 单行内容的最大长度，超出部分截断
+const GREP_MAX_LINE_LEN: usize = 1024;
+/// 保留的 grep 错误输出字节数
+const GREP_STDERR_LIMIT: usize = 4096;
+/// 未遇到换行时缓存的最大字节数，超长行（如压缩后的脚本）只取开头，其余部分丢弃到下一个换行
+const GREP_MAX_PENDING_BYTES: usize = 64 * 1024;
+
+#[derive(Clone, serde::Serialize)]
+#[serde(
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase",
+    tag = "event",
+    content = "data"
+)]
+pub enum RemoteGrepEvent {
+    Match {
+        path: String,
+        line_number: u64,
+        line: String,
+    },
+    Finished {
+        matched: u32,
+        /// 达到 max_results 后提前结束
+        truncated: bool,
+        /// 实际使用的方式: "grep" 或 "sftp"
+        backend: String,
+    },
+    Cancelled,
+}
+
+/// 内容搜索的结束状态
+enum GrepOutcome {
+    Done { matched: u32, truncated: bool },
+    /// 远端 grep 不可用（命令不存在或不支持参数）
+    Unsupported,
+    Cancelled,
+}
+
+/// 在远端目录中递归搜索文件内容，匹配行通过 channel 逐条推送
+#[tauri::command]
+pub async fn ssh_remote_grep(
+    task_id: &str,
+    session_id: &str,
+    dir: &str,
+    options: RemoteGrepOptions,
+    on_grep_event: tauri::ipc::Channel<RemoteGrepEvent>,
+) -> Result<u32, String> {
+    if options.pattern.is_empty() {
+        return Err("搜索内容不能为空".to_string());
+    }
+    let token = CancellationToken::new();
+    {
+        let mut map = SEARCH_TASKS.lock().await;
+        map.insert(task_id.into(), token.clone());
+    }
+
+    let result = async {
+        if options.backend != "sftp" {
+            match grep_exec(session_id, dir, &options, &token, &on_grep_event).await? {
+                GrepOutcome::Unsupported if options.backend == "auto" => {
+                    info!("Remote grep unavailable for {}, falling back to sftp", session_id);
+                }
+                GrepOutcome::Unsupported => return Err("远端 grep 不可用".to_string()),
+                outcome => return Ok((outcome, "grep")),
+            }
+        }
+        let outcome = grep_sftp(session_id, dir, &options, &token, &on_grep_event).await?;
+        Ok((outcome, "sftp"))
+    }
+    .await;
+
+    SEARCH_TASKS.lock().await.remove(task_id);
+    match result? {
+        (GrepOutcome::Done { matched, truncated }, backend) => {
+            let _ = on_grep_event.send(RemoteGrepEvent::Finished {
+                matched,
+                truncated,
+                backend: backend.to_string(),
+            });
+            Ok(matched)
+        }
+        _ => {
+            let _ = on_grep_event.send(RemoteGrepEvent::Cancelled);
+            Err("搜索已取消".to_string())
+        }
+    }
+}
+
+/// 通过 exec 运行 `grep -rIHnZ`，输出格式为 `path\0line:text`
+async fn grep_exec(
+    session_id: &str,
+    dir: &str,
+    options: &RemoteGrepOptions,
+    token: &CancellationToken,
+    on_grep_event: &tauri::ipc::Channel<RemoteGrepEvent>,
+) -> Result<GrepOutcome, String> {
+    let mut command = String::from("grep -rIHnZ");
+    command.push_str(if options.fixed_string { " -F" } else { " -E" });
+    if options.ignore_case {
+        command.push_str(" -i");
+    }
+    for include in options.include.iter().filter(|p| !p.is_empty()) {
+        command.push_str(&format!(" --include={}", shell_quote(include)));
+    }
+    for exclude in options.exclude_dirs.iter().filter(|p| !p.is_empty()) {
+        command.push_str(&format!(" --exclude-dir={}", shell_quote(exclude)));
+    }
+    command.push_str(&format!(" -e {} -- {}", shell_quote(&options.pattern), shell_quote(dir)));
+
+    let mut channel = exec_channel(session_id, &command).await?;
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut stderr: Vec<u8> = Vec::new();
+    let mut matched: u32 = 0;
+    let mut exit_status: Option<u32> = None;
+    // 超长行已按截断发送，丢弃其剩余部分
+    let mut skipping = false;
+
+    loop {
+        let msg = tokio::select! {
+            _ = token.cancelled() => {
+                let _ = channel.close().await;
+                return Ok(GrepOutcome::Cancelled);
+            }
+            msg = channel.wait() => msg,
+        };
+        match msg {
+            Some(russh::ChannelMsg::Data { ref data }) => {
+                let mut data: &[u8] = data;
+                if skipping {
+                    let Some(pos) = data.iter().position(|b| *b == b'\n') else {
+                        continue;
+                    };
+                    data = &data[pos + 1..];
+                    skipping = false;
+                }
+                buffer.extend_from_slice(data);
+                while let Some(pos) = buffer.iter().position(|b| *b == b'\n') {
+                    let line: Vec<u8> = buffer.drain(..=pos).collect();
+                    if emit_match(&line, &mut matched, on_grep_event) && matched >= options.max_results {
+                        let _ = channel.close().await;
+                        return Ok(GrepOutcome::Done { matched, truncated: true });
+                    }
+                }
+                if buffer.len() > GREP_MAX_PENDING_BYTES {
+                    let line = std::mem::take(&mut buffer);
+                    skipping = true;
+                    if emit_match(&line, &mut matched, on_grep_event) && matched >= options.max_results {
+                        let _ = channel.close().await;
+                        return Ok(GrepOutcome::Done { matched, truncated: true });
+                    }
+                }
+            }
+            Some(russh::ChannelMsg::ExtendedData { ref data, ext: 1 }) => {
+                // 无权限的文件可能产生大量错误输出，只保留开头用于判断
+                let room = GREP_STDERR_LIMIT.saturating_sub(stderr.len());
+                stderr.extend_from_slice(&data[..data.len().min(room)]);
+            }
+            Some(russh::ChannelMsg::ExitStatus { exit_status: status }) => {
+                exit_status = Some(status);
+            }
+            Some(russh::ChannelMsg::Close) | None => break,
+            _ => {}
+        }
+    }
+
+    // 0: 有匹配；1: 无匹配；2: 出错，有匹配时通常只是部分文件无权限读取；127: 命令不存在
+    // 只有 grep 不存在或不支持所用选项（如 busybox 精简版）时才回退到 sftp
+    // 出错且没有任何匹配（目录不存在、正则无效等）时返回错误信息
+    let stderr = String::from_utf8_lossy(&stderr);
+    let bad_option = ["unrecognized option", "invalid option", "illegal option"]
+        .iter()
+        .any(|s| stderr.contains(s));
+    match exit_status {
+        Some(127) => Ok(GrepOutcome::Unsupported),
+        Some(2) if bad_option && matched == 0 => Ok(GrepOutcome::Unsupported),
+        Some(0..=1) => Ok(GrepOutcome::Done { matched, truncated: false }),
+        Some(2) if matched > 0 => Ok(GrepOutcome::Done { matched, truncated: false }),
+        Some(status) => Err(match stderr.trim() {
+            "" => format!("grep exited with status {}", status),
+            message => message.to_string(),
+        }),
+        None => Err("grep terminated without exit status".to_string()),
+    }
+}
+
+/// 解析一行 grep 输出并发送匹配，返回是否为有效的匹配行
+fn emit_match(line: &[u8], matched: &mut u32, on_grep_event: &tauri::ipc::Channel<RemoteGrepEvent>) -> bool {
+    let Some((path, line_number, text)) = parse_grep_line(line) else {
+        return false;
+    };
+    let _ = on_grep_event.send(RemoteGrepEvent::Match { path, line_number, line: text });
+    *matched += 1;
+    true
+}
+
+/// 解析 `path\0line:text\n`
+fn parse_grep_line(line: &[u8]) -> Option<(String, u64, String)> {
+    let nul = line.iter().position(|b| *b == 0)?;
+    let path = String::from_utf8_lossy(&line[..nul]).to_string();
+    let rest = &line[nul + 1..];
+    let colon = rest.iter().position(|b| *b == b':')?;
+    let line_number = std::str::from_utf8(&rest[..colon]).ok()?.parse().ok()?;
+    let text = String::from_utf8_lossy(&rest[colon + 1..]);
+    Some((path, line_number, truncate_line(text.trim_end_matches(['\r', '\n']))))
+}
+
+fn truncate_line(text: &str) -> String {
+    match text.char_indices().nth(GREP_MAX_LINE_LEN) {
+        Some((idx, _)) => text[..idx].to_string(),
+        None => text.to_string(),
+    }
+}
+
+/// 纯 sftp 回退：遍历目录并在本地逐行匹配
+async fn grep_sftp(
+    session_id: &str,
+    dir: &str,
+    options: &RemoteGrepOptions,
+    token: &CancellationToken,
+    on_grep_event: &tauri::ipc::Channel<RemoteGrepEvent>,
+) -> Result<GrepOutcome, String> {
+    let sftp = ssh_get_sftp(session_id).await?;
+    let pattern = if options.fixed_string {
+        regex::escape(&options.pattern)
+    } else {
+        options.pattern.clone()
+    };
+    let regex = regex::RegexBuilder::new(&pattern)
+        .case_insensitive(options.ignore_case)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let includes = build_excludes(&options.include)?;
+    let exclude_dirs = build_excludes(&options.exclude_dirs)?;
+
+    let mut queue: VecDeque<String> = VecDeque::new();
+    queue.push_back(dir.to_string());
+    let mut matched: u32 = 0;
+
+    while let Some(current) = queue.pop_front() {
+        if token.is_cancelled() {
+            return Ok(GrepOutcome::Cancelled);
+        }
+        let Ok(entries) = sftp.read_dir(current.as_str()).await else {
+            continue;
+        };
+        for entry in entries {
+            let filename = entry.file_name();
+            let metadata = entry.metadata();
+            let path = remote_join(&current, &filename);
+            if metadata.file_type().is_dir() {
+                if !exclude_dirs.iter().any(|p| p.matches(&filename)) {
+                    queue.push_back(path);
+                }
+                continue;
+            }
+            if !metadata.file_type().is_file()
+                || metadata.size.unwrap_or(0) > options.max_file_size
+                || (!includes.is_empty() && !includes.iter().any(|p| p.matches(&filename)))
+            {
+                continue;
+            }
+
+            let content = tokio::select! {
+                _ = token.cancelled() => return Ok(GrepOutcome::Cancelled),
+                res = sftp.read(path.as_str()) => res,
+            };
+            let Ok(content) = content else {
+                continue;
+            };
+            // 与 grep -I 一致，跳过二进制文件
+            if content[..content.len().min(8000)].contains(&0) {
+                continue;
+            }
+            for (idx, raw_line) in content.split(|b| *b == b'\n').enumerate() {
+                let text = String::from_utf8_lossy(raw_line);
+                if !regex.is_match(&text) {
+                    continue;
+                }
+                let _ = on_grep_event.send(RemoteGrepEvent::Match {
+                    path: path.clone(),
+                    line_number: idx as u64 + 1,
+                    line: truncate_line(text.trim_end_matches('\r')),
+                });
+                matched += 1;
+                if matched >= options.max_results {
+                    return Ok(GrepOutcome::Done { matched, truncated: true });
+                }
+            }
+        }
+    }
+    Ok(GrepOutcome::Done { matched, truncated: false })
+}
@@ -1,18 +1,31 @@
-use crate::ssh::ssh_get_sftp;
+use crate::checksum::remote_file_hash;
+use crate::scp;
+use crate::text;
+use crate::transfer::{percent, transfer_speed};
+use crate::ssh::{ssh_get_raw_sftp, ssh_get_sftp, RawSftp, SFTP_UNAVAILABLE};
 use russh_sftp::client::SftpSession;
-use serde::Serialize;
+use data_encoding::HEXLOWER;
+use ring::rand::{SecureRandom, SystemRandom};
+use russh_sftp::client::fs::Metadata;
+use russh_sftp::protocol::{FileAttributes, Packet, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
 use std::sync::Arc;
 use tokio::io::{AsyncReadExt};
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
 use tokio_util::sync::CancellationToken;
 use tokio::sync::Mutex;
+use tokio::task::JoinSet;
+use std::time::{Duration, Instant};
 
+/// 打开文件传输通道，返回实际使用的后端："sftp" 或 "scp"（服务端不提供 sftp 子系统时）
 #[tauri::command]
-pub async fn ssh_sftp_open(session_id: &str) -> Result<(), String> {
+pub async fn ssh_sftp_open(session_id: &str) -> Result<String, String> {
     let sftp_session: Result<Arc<SftpSession>, String> = ssh_get_sftp(session_id).await;
     match sftp_session {
-        Ok(_) => Ok(()),
+        Ok(_) => Ok("sftp".to_string()),
+        Err(err) if err == SFTP_UNAVAILABLE => Ok("scp".to_string()),
         Err(err) => Err(err),
     }
 }
@@ -44,6 +57,17 @@ pub async fn ssh_sftp_listdir(session_id: &str, dir: &str) -> Result<Vec<SftpFil
     Ok(result)
 }
 
+/// 拼接远端路径
+pub fn remote_join(dir: &str, name: &str) -> String {
+    if dir.is_empty() || dir == "." {
+        name.to_string()
+    } else if dir.ends_with('/') {
+        format!("{}{}", dir, name)
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
 #[tauri::command]
 pub async fn ssh_sftp_canonicalize(session_id: &str, file_path: &str) -> Result<String, String> {
     let sftp_session: Result<Arc<SftpSession>, String> = ssh_get_sftp(session_id).await;
@@ -88,12 +112,22 @@ pub async fn ssh_sftp_read(
     on_down_event: tauri::ipc::Channel<SftpDownloadEvent>,
 ) -> Result<String, String> {
     // 1. 获取 sftp 注册任务
-    let sftp = ssh_get_sftp(session_id).await?;
+    let sftp = match ssh_get_sftp(session_id).await {
+        Ok(sftp) => Some(sftp),
+        Err(err) if err == SFTP_UNAVAILABLE => None,
+        Err(err) => return Err(err),
+    };
     let token = CancellationToken::new();
     {
         let mut map = DOWNLOAD_TASKS.lock().await;
         map.insert(task_id.into(), token.clone());
     }
+    let Some(sftp) = sftp else {
+        // 服务端不支持 sftp 子系统，改用 scp 读取
+        let result = scp::scp_read_to_channel(session_id, file_path, &token, &on_down_event).await;
+        DOWNLOAD_TASKS.lock().await.remove(task_id);
+        return result.map(|_| "ok".to_string());
+    };
 
     // 2. 打开远端文件
     let mut remote_file = sftp
@@ -164,17 +198,446 @@ pub async fn ssh_sftp_read_cancel(task_id: String) -> Result<(), String> {
 }
 
 
-/// 读取文本文件内容，直接返回字符串
+/// 整文件读取文本的大小上限，超出时需使用 ssh_sftp_read_text_range 分页读取
+const TEXT_MAX_SIZE: u64 = 5 * 1024 * 1024;
+
+/// 单页读取的最大字节数
+const TEXT_PAGE_MAX_SIZE: u64 = 1024 * 1024;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SftpTextFile {
+    /// 文本内容（换行已统一为 \n）
+    pub content: String,
+    pub encoding: String,
+    pub bom: bool,
+    /// 原文件换行符: "LF"、"CRLF" 或 "CR"
+    pub line_ending: String,
+    pub size: u64,
+    pub mtime: u32,
+    /// 文件内容的 SHA-256，保存时用于冲突检测
+    pub sha256: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SftpTextPage {
+    /// 本页文本（换行已统一为 \n）
+    pub content: String,
+    pub encoding: String,
+    pub bom: bool,
+    pub line_ending: String,
+    pub file_size: u64,
+    pub offset: u64,
+    /// 下一页的起始偏移
+    pub next_offset: u64,
+    pub eof: bool,
+}
+
+/// 从 offset 开始读取最多 length 字节，返回数据和文件大小
+async fn read_range(
+    sftp: &SftpSession,
+    file_path: &str,
+    offset: u64,
+    length: u64,
+) -> Result<(Vec<u8>, u64), String> {
+    use tokio::io::AsyncSeekExt;
+
+    let mut file = sftp.open(file_path).await.map_err(|e| e.to_string())?;
+    let file_size = file
+        .metadata()
+        .await
+        .map_err(|e| e.to_string())?
+        .size
+        .unwrap_or(0);
+    if offset >= file_size {
+        return Ok((Vec::new(), file_size));
+    }
+    file.seek(std::io::SeekFrom::Start(offset))
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut buf = Vec::with_capacity(length.min(file_size - offset) as usize);
+    (&mut file)
+        .take(length)
+        .read_to_end(&mut buf)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok((buf, file_size))
+}
+
+/// 读取整个文本文件并确定编码，超过 TEXT_MAX_SIZE 或为二进制文件时报错
+async fn load_text_file(
+    sftp: &SftpSession,
+    file_path: &str,
+    encoding: Option<&str>,
+) -> Result<(Vec<u8>, text::TextEncoding, Metadata), String> {
+    let metadata = sftp.metadata(file_path).await.map_err(|e| e.to_string())?;
+    let size = metadata.size.unwrap_or(0);
+    if size > TEXT_MAX_SIZE {
+        return Err(format!(
+            "File is too large to open as text ({} bytes, limit {} bytes)",
+            size, TEXT_MAX_SIZE
+        ));
+    }
+
+    let (data, _) = read_range(sftp, file_path, 0, TEXT_MAX_SIZE).await?;
+    let detected = text::detect_encoding(&data, true);
+    let enc = match encoding {
+        Some(label) => {
+            let bom = detected.as_ref().map(|d| d.bom).unwrap_or(false);
+            text::encoding_for_label(label, bom)?
+        }
+        None => detected?,
+    };
+    Ok((data, enc, metadata))
+}
+
+/// 读取整个文本文件并按编码解码，换行统一为 \n
+async fn read_text_file(
+    sftp: &SftpSession,
+    file_path: &str,
+    encoding: Option<&str>,
+) -> Result<SftpTextFile, String> {
+    let (data, enc, metadata) = load_text_file(sftp, file_path, encoding).await?;
+    let raw = text::decode(&data[text::bom_len(&data)..], &enc);
+    Ok(SftpTextFile {
+        line_ending: text::detect_line_ending(&raw).to_string(),
+        content: text::normalize_line_endings(&raw),
+        encoding: enc.name(),
+        bom: enc.bom,
+        size: metadata.size.unwrap_or(0),
+        mtime: metadata.mtime.unwrap_or(0),
+        sha256: sha256_hex(&data),
+    })
+}
+
+/// 读取文本文件内容，直接返回字符串（自动识别编码，保留原换行符）
 #[tauri::command]
 pub async fn ssh_sftp_read_text(
     session_id: &str,
     file_path: &str,
 ) -> Result<String, String> {
     let sftp = ssh_get_sftp(session_id).await?;
-    let mut remote_file = sftp.open(file_path).await.map_err(|e| e.to_string())?;
-    let mut content = String::new();
-    remote_file.read_to_string(&mut content).await.map_err(|e| e.to_string())?;
-    Ok(content)
+    let (data, enc, _) = load_text_file(&sftp, file_path, None).await?;
+    Ok(text::decode(&data[text::bom_len(&data)..], &enc))
+}
+
+/// 以文本方式打开文件，返回内容及编码、BOM、换行符等信息，保存时原样传回 ssh_sftp_write_text
+/// encoding 为空时自动检测
+#[tauri::command]
+pub async fn ssh_sftp_open_text(
+    session_id: &str,
+    file_path: &str,
+    encoding: Option<String>,
+) -> Result<SftpTextFile, String> {
+    let sftp = ssh_get_sftp(session_id).await?;
+    read_text_file(&sftp, file_path, encoding.as_deref()).await
+}
+
+/// 分页读取大文本文件，页边界对齐到行尾；encoding 为空时根据文件开头检测
+#[tauri::command]
+pub async fn ssh_sftp_read_text_range(
+    session_id: &str,
+    file_path: &str,
+    offset: u64,
+    length: u64,
+    encoding: Option<String>,
+) -> Result<SftpTextPage, String> {
+    let sftp = ssh_get_sftp(session_id).await?;
+
+    let (head, file_size) =
+        read_range(&sftp, file_path, 0, text::DETECT_SAMPLE_SIZE as u64).await?;
+    let complete = head.len() as u64 >= file_size;
+    let enc = match encoding.as_deref() {
+        Some(label) => {
+            let bom = text::detect_encoding(&head, complete)
+                .map(|d| d.bom)
+                .unwrap_or(false);
+            text::encoding_for_label(label, bom)?
+        }
+        None => text::detect_encoding(&head, complete)?,
+    };
+
+    // 跳过 BOM；UTF-16 的偏移和长度按 2 字节的码元对齐
+    let offset = offset.max(text::bom_len(&head) as u64);
+    let length = length.clamp(1, TEXT_PAGE_MAX_SIZE);
+    let (offset, length) = if enc.is_utf16() {
+        (offset & !1, (length & !1).max(2))
+    } else {
+        (offset, length)
+    };
+    let (data, file_size) = read_range(&sftp, file_path, offset, length).await?;
+    let reached_end = offset + data.len() as u64 >= file_size;
+    let used = if reached_end {
+        data.len()
+    } else {
+        // 页长小于一个字符时整页返回，避免无法前进
+        match text::page_boundary(&data, &enc) {
+            0 => data.len(),
+            n => n,
+        }
+    };
+
+    let raw = text::decode(&data[..used], &enc);
+    let next_offset = offset + used as u64;
+    Ok(SftpTextPage {
+        line_ending: text::detect_line_ending(&raw).to_string(),
+        content: text::normalize_line_endings(&raw),
+        encoding: enc.name(),
+        bom: enc.bom,
+        file_size,
+        offset,
+        next_offset,
+        eof: next_offset >= file_size,
+    })
+}
+
+/// 按指定编码、BOM 和换行符写回文本文件（参数取自 ssh_sftp_open_text 的返回值）
+#[tauri::command]
+pub async fn ssh_sftp_write_text(
+    session_id: &str,
+    file_path: &str,
+    content: &str,
+    encoding: &str,
+    bom: bool,
+    line_ending: &str,
+) -> Result<(), String> {
+    let enc = text::encoding_for_label(encoding, bom)?;
+    let data = text::encode(content, &enc, line_ending)?;
+
+    let sftp = ssh_get_sftp(session_id).await?;
+    let mut file = sftp.create(file_path).await.map_err(|e| e.to_string())?;
+
+    use tokio::io::AsyncWriteExt;
+    file.write_all(&data).await.map_err(|e| e.to_string())?;
+    file.shutdown().await.map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// 远端文件在打开后被他人修改时，ssh_sftp_save_text 返回的错误前缀
+pub const SAVE_CONFLICT: &str = "Remote file has been modified";
+
+const POSIX_RENAME: &str = "posix-rename@openssh.com";
+
+/// 打开文件时记录的版本（取自 ssh_sftp_open_text），保存时用于冲突检测
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SftpFileVersion {
+    pub size: u64,
+    pub mtime: u32,
+    /// mtime 变化但内容未变时（如 touch）用于避免误报
+    pub sha256: Option<String>,
+}
+
+/// 保存后的文件版本，作为下一次保存的 expected
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SftpSaveResult {
+    pub size: u64,
+    pub mtime: u32,
+    pub sha256: String,
+}
+
+/// 安全保存文本文件：
+/// 1. 校验远端文件的 size/mtime/sha256 与 expected 一致（force 为 true 时跳过）
+/// 2. 写入同目录下的临时文件并 fsync
+/// 3. 复制原文件的权限和属主
+/// 4. 原子重命名覆盖原文件
+///
+/// 任一步骤失败时原文件保持不变
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn ssh_sftp_save_text(
+    session_id: &str,
+    file_path: &str,
+    content: &str,
+    encoding: &str,
+    bom: bool,
+    line_ending: &str,
+    expected: Option<SftpFileVersion>,
+    force: bool,
+) -> Result<SftpSaveResult, String> {
+    let enc = text::encoding_for_label(encoding, bom)?;
+    let data = text::encode(content, &enc, line_ending)?;
+    sftp_atomic_write(session_id, file_path, &data, expected.as_ref(), force).await
+}
+
+/// 原子写入远端文件（ssh_sftp_save_text 的实现，外部编辑器回传时同样使用）
+pub async fn sftp_atomic_write(
+    session_id: &str,
+    file_path: &str,
+    data: &[u8],
+    expected: Option<&SftpFileVersion>,
+    force: bool,
+) -> Result<SftpSaveResult, String> {
+    let sftp = ssh_get_sftp(session_id).await?;
+
+    // 符号链接写到链接目标，避免把链接替换成普通文件
+    let target = match sftp.symlink_metadata(file_path).await {
+        Ok(meta) if meta.file_type().is_symlink() => sftp
+            .canonicalize(file_path)
+            .await
+            .map_err(|e| e.to_string())?,
+        _ => file_path.to_string(),
+    };
+    let current = sftp.metadata(target.as_str()).await.ok();
+
+    if !force {
+        check_conflict(&sftp, &target, current.as_ref(), expected).await?;
+    }
+
+    let tmp = temp_sibling(&target)?;
+    if let Err(e) = write_and_replace(session_id, &sftp, &target, &tmp, data, current.as_ref()).await {
+        let _ = sftp.remove_file(tmp.as_str()).await;
+        return Err(e);
+    }
+
+    let metadata = sftp.metadata(target.as_str()).await.map_err(|e| e.to_string())?;
+    Ok(SftpSaveResult {
+        size: metadata.size.unwrap_or(data.len() as u64),
+        mtime: metadata.mtime.unwrap_or(0),
+        sha256: sha256_hex(data),
+    })
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    HEXLOWER.encode(ring::digest::digest(&ring::digest::SHA256, data).as_ref())
+}
+
+async fn check_conflict(
+    sftp: &SftpSession,
+    path: &str,
+    current: Option<&Metadata>,
+    expected: Option<&SftpFileVersion>,
+) -> Result<(), String> {
+    let Some(expected) = expected else {
+        return Ok(());
+    };
+    let Some(current) = current else {
+        return Err(format!("{}: file has been deleted", SAVE_CONFLICT));
+    };
+
+    let size = current.size.unwrap_or(0);
+    let mtime = current.mtime.unwrap_or(0);
+    if size == expected.size && mtime == expected.mtime {
+        return Ok(());
+    }
+    if size == expected.size {
+        if let Some(hash) = &expected.sha256 {
+            if remote_file_hash(sftp, path, &ring::digest::SHA256).await?.eq_ignore_ascii_case(hash) {
+                return Ok(());
+            }
+        }
+    }
+    Err(format!(
+        "{}: size {} -> {}, mtime {} -> {}",
+        SAVE_CONFLICT, expected.size, size, expected.mtime, mtime
+    ))
+}
+
+/// 同目录下的临时文件名，保证 rename 不跨文件系统
+pub fn temp_sibling(path: &str) -> Result<String, String> {
+    let (dir, name) = match path.rfind('/') {
+        Some(pos) => (&path[..pos + 1], &path[pos + 1..]),
+        None => ("", path),
+    };
+    let mut suffix = [0u8; 4];
+    SystemRandom::new()
+        .fill(&mut suffix)
+        .map_err(|_| "Failed to generate temp file name".to_string())?;
+    Ok(format!("{}.{}.{}.tmp", dir, name, HEXLOWER.encode(&suffix)))
+}
+
+async fn write_and_replace(
+    session_id: &str,
+    sftp: &SftpSession,
+    target: &str,
+    tmp: &str,
+    data: &[u8],
+    current: Option<&Metadata>,
+) -> Result<(), String> {
+    use russh_sftp::protocol::OpenFlags;
+    use tokio::io::AsyncWriteExt;
+
+    // 覆盖已有文件时临时文件先以 0600 创建，写完后再设置为原文件的权限，
+    // 避免 0600 的文件（密钥、.env 等）在写入期间按默认 umask 对其他用户可读
+    let mut attrs = FileAttributes::empty();
+    if current.is_some() {
+        attrs.permissions = Some(0o600);
+    }
+    let mut file = sftp
+        .open_with_flags_and_attributes(
+            tmp,
+            OpenFlags::CREATE | OpenFlags::EXCLUDE | OpenFlags::WRITE,
+            attrs,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    file.write_all(data).await.map_err(|e| e.to_string())?;
+    file.sync_all().await.map_err(|e| e.to_string())?;
+    file.shutdown().await.map_err(|e| e.to_string())?;
+
+    if let Some(current) = current {
+        // 普通用户通常无法 chown，失败时仅保留权限
+        let mut attrs = FileAttributes::empty();
+        attrs.permissions = current.permissions;
+        attrs.uid = current.uid;
+        attrs.gid = current.gid;
+        if sftp.set_metadata(tmp, attrs).await.is_err() {
+            let mut attrs = FileAttributes::empty();
+            attrs.permissions = current.permissions;
+            sftp.set_metadata(tmp, attrs)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    replace_file(session_id, sftp, tmp, target, current.is_some()).await
+}
+
+/// 用 from 覆盖 to：优先使用 posix-rename 扩展（原子操作），
+/// 否则 SFTP v3 的 rename 在目标存在时会失败，退化为 备份 → 重命名 → 删除备份
+async fn replace_file(
+    session_id: &str,
+    sftp: &SftpSession,
+    from: &str,
+    to: &str,
+    exists: bool,
+) -> Result<(), String> {
+    if let Ok(raw) = ssh_get_raw_sftp(session_id).await {
+        if raw.extensions.contains_key(POSIX_RENAME) {
+            let mut data = Vec::with_capacity(from.len() + to.len() + 8);
+            put_ssh_string(&mut data, from.as_bytes());
+            put_ssh_string(&mut data, to.as_bytes());
+            return match raw.session.extended(POSIX_RENAME, data).await {
+                Ok(Packet::Status(status)) if status.status_code == StatusCode::Ok => Ok(()),
+                Ok(Packet::Status(status)) => Err(status.error_message),
+                Ok(_) => Err("Unexpected reply to posix-rename".to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+        }
+    }
+
+    if !exists {
+        return sftp.rename(from, to).await.map_err(|e| e.to_string());
+    }
+    let backup = format!("{}.bak", from);
+    sftp.rename(to, backup.as_str())
+        .await
+        .map_err(|e| e.to_string())?;
+    if let Err(e) = sftp.rename(from, to).await {
+        let _ = sftp.rename(backup.as_str(), to).await;
+        return Err(e.to_string());
+    }
+    let _ = sftp.remove_file(backup).await;
+    Ok(())
+}
+
+/// 按 SSH 协议编码字符串（u32 长度 + 内容），用于构造扩展请求
+pub fn put_ssh_string(buf: &mut Vec<u8>, data: &[u8]) {
+    buf.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    buf.extend_from_slice(data);
 }
 
 #[tauri::command]
@@ -245,16 +708,271 @@ pub async fn ssh_sftp_write_chunk(
     Ok(())
 }
 
+#[derive(Clone, serde::Serialize)]
+#[serde(
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase",
+    tag = "event",
+    content = "data"
+)]
+pub enum SftpUploadEvent {
+    Process {
+        val: u32,
+        /// 已确认写入的字节数
+        transferred: u64,
+        total: u64,
+        /// 平均速率 (bytes/s)
+        speed: f64,
+    },
+    Finished {
+        total: u64,
+        elapsed_ms: u64,
+    },
+    Cancelled,
+}
+
+static UPLOAD_TASKS: Lazy<Mutex<HashMap<String, CancellationToken>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 单个写请求的数据大小
+const UPLOAD_CHUNK_SIZE: usize = 32 * 1024;
+/// 同时在途的写请求数量上下限，实际数量按已确认的速率调整
+const UPLOAD_MIN_INFLIGHT: usize = 4;
+const UPLOAD_MAX_INFLIGHT: usize = 64;
+/// 在途数据量按该时长内可确认的字节数计算，需远小于 sftp 请求的 10 秒超时，避免慢速链路上排在窗口末尾的请求超时
+const UPLOAD_WINDOW_SECS: f64 = 2.0;
+
+/// 流水线上传：远端文件只打开一次，同时保持多个写请求在途，本地文件由 Rust 直接读取
 #[tauri::command]
-pub async fn ssh_sftp_mkdir(session_id: &str, dir: &str) -> Result<(), String> {
-    let sftp = ssh_get_sftp(session_id).await?;
-    sftp.create_dir(dir).await.map_err(|e| e.to_string())
+pub async fn ssh_sftp_upload(
+    task_id: &str,
+    session_id: &str,
+    local_path: &str,
+    remote_path: &str,
+    on_up_event: tauri::ipc::Channel<SftpUploadEvent>,
+) -> Result<String, String> {
+    let raw = match ssh_get_raw_sftp(session_id).await {
+        Ok(raw) => Some(raw),
+        Err(err) if err == SFTP_UNAVAILABLE => None,
+        Err(err) => return Err(err),
+    };
+    let token = CancellationToken::new();
+    {
+        let mut map = UPLOAD_TASKS.lock().await;
+        map.insert(task_id.into(), token.clone());
+    }
+
+    let result = match raw {
+        Some(raw) => upload_file(raw, local_path, remote_path, &token, &on_up_event).await,
+        // 服务端不支持 sftp 子系统，改用 scp 上传
+        None => scp::scp_upload_file(session_id, local_path, remote_path, &token, &on_up_event).await,
+    };
+
+    UPLOAD_TASKS.lock().await.remove(task_id);
+    result.map(|_| "ok".to_string())
+}
+
+#[tauri::command]
+pub async fn ssh_sftp_upload_cancel(task_id: String) -> Result<(), String> {
+    let mut map = UPLOAD_TASKS.lock().await;
+
+    if let Some(token) = map.remove(&task_id) {
+        token.cancel();
+        Ok(())
+    } else {
+        Err("任务不存在".to_string())
+    }
+}
+
+async fn upload_file(
+    raw: Arc<RawSftp>,
+    local_path: &str,
+    remote_path: &str,
+    token: &CancellationToken,
+    on_up_event: &tauri::ipc::Channel<SftpUploadEvent>,
+) -> Result<(), String> {
+    let started = Instant::now();
+    let mut last_progress = 0;
+    let mut last_emit = Instant::now();
+
+    let result = sftp_upload_file(
+        &raw,
+        Path::new(local_path),
+        remote_path,
+        token,
+        &mut |acked, total_size| {
+            let progress = percent(acked, total_size);
+            // 进度变化或超过 500ms 时才发送，避免过于频繁
+            if progress != last_progress || last_emit.elapsed() >= Duration::from_millis(500) {
+                last_progress = progress;
+                last_emit = Instant::now();
+                let _ = on_up_event.send(SftpUploadEvent::Process {
+                    val: progress,
+                    transferred: acked,
+                    total: total_size,
+                    speed: transfer_speed(acked, started),
+                });
+            }
+        },
+    )
+    .await;
+
+    let acked = match result {
+        Ok(acked) => acked,
+        Err(e) => {
+            if token.is_cancelled() {
+                let _ = on_up_event.send(SftpUploadEvent::Cancelled);
+            }
+            return Err(e);
+        }
+    };
+    let _ = on_up_event.send(SftpUploadEvent::Process {
+        val: 100,
+        transferred: acked,
+        total: acked,
+        speed: transfer_speed(acked, started),
+    });
+    let _ = on_up_event.send(SftpUploadEvent::Finished {
+        total: acked,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    });
+    Ok(())
+}
+
+/// 流水线上传单个本地文件，on_progress 参数为（已确认字节数，文件总大小），返回上传字节数
+pub async fn sftp_upload_file(
+    raw: &Arc<RawSftp>,
+    local_path: &Path,
+    remote_path: &str,
+    token: &CancellationToken,
+    on_progress: &mut (dyn FnMut(u64, u64) + Send),
+) -> Result<u64, String> {
+    use russh_sftp::protocol::OpenFlags;
+
+    // 1. 打开本地文件
+    let mut local_file = tokio::fs::File::open(local_path)
+        .await
+        .map_err(|e| e.to_string())?;
+    let total_size = local_file
+        .metadata()
+        .await
+        .map_err(|e| e.to_string())?
+        .len();
+
+    // 2. 远端文件只打开一次
+    let handle = raw
+        .session
+        .open(
+            remote_path,
+            OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNCATE,
+            FileAttributes::empty(),
+        )
+        .await
+        .map_err(|e| e.to_string())?
+        .handle;
+
+    // 3. 保持多个写请求在途，按确认的字节数计算进度和窗口大小
+    let mut inflight: JoinSet<Result<u64, String>> = JoinSet::new();
+    let mut offset: u64 = 0;
+    let mut acked: u64 = 0;
+    let mut eof = false;
+    let mut window = UPLOAD_MIN_INFLIGHT;
+    let started = Instant::now();
+
+    let result = async {
+        loop {
+            while !eof && inflight.len() < window {
+                let mut buffer = vec![0u8; UPLOAD_CHUNK_SIZE];
+                let n = local_file.read(&mut buffer).await.map_err(|e| e.to_string())?;
+                if n == 0 {
+                    eof = true;
+                    break;
+                }
+                buffer.truncate(n);
+
+                let raw = raw.clone();
+                let handle = handle.clone();
+                let chunk_offset = offset;
+                inflight.spawn(async move {
+                    raw.session
+                        .write(handle, chunk_offset, buffer)
+                        .await
+                        .map(|_| n as u64)
+                        .map_err(|e| e.to_string())
+                });
+                offset += n as u64;
+            }
+
+            tokio::select! {
+                _ = token.cancelled() => {
+                    return Err("上传已取消".to_string());
+                }
+
+                joined = inflight.join_next() => {
+                    let Some(joined) = joined else {
+                        // 没有在途请求且本地已读完
+                        break;
+                    };
+                    acked += joined.map_err(|e| e.to_string())??;
+                    on_progress(acked, total_size);
+                    let rate = transfer_speed(acked, started);
+                    window = ((rate * UPLOAD_WINDOW_SECS) as usize / UPLOAD_CHUNK_SIZE)
+                        .clamp(UPLOAD_MIN_INFLIGHT, UPLOAD_MAX_INFLIGHT);
+                }
+            }
+        }
+        Ok::<(), String>(())
+    }
+    .await;
+
+    // 4. 无论成功与否都关闭远端句柄
+    inflight.abort_all();
+    let _ = raw.session.close(handle).await;
+    result.map(|_| acked)
+}
+
+/// 下载单个远端文件到本地路径，on_progress 参数为（已下载字节数，文件总大小），返回下载字节数
+pub async fn sftp_download_file(
+    sftp: &SftpSession,
+    remote_path: &str,
+    local_path: &Path,
+    token: &CancellationToken,
+    on_progress: &mut (dyn FnMut(u64, u64) + Send),
+) -> Result<u64, String> {
+    use tokio::io::AsyncWriteExt;
+
+    let mut remote_file = sftp.open(remote_path).await.map_err(|e| e.to_string())?;
+    let total_size = remote_file
+        .metadata()
+        .await
+        .map_err(|e| e.to_string())?
+        .len();
+    let mut local_file = tokio::fs::File::create(local_path)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut buffer = vec![0u8; 32 * 1024];
+    let mut downloaded: u64 = 0;
+    loop {
+        let n = tokio::select! {
+            _ = token.cancelled() => return Err("下载已取消".to_string()),
+            read_res = remote_file.read(&mut buffer) => read_res.map_err(|e| e.to_string())?,
+        };
+        if n == 0 {
+            break;
+        }
+        local_file.write_all(&buffer[..n]).await.map_err(|e| e.to_string())?;
+        downloaded += n as u64;
+        on_progress(downloaded, total_size);
+    }
+    local_file.flush().await.map_err(|e| e.to_string())?;
+    Ok(downloaded)
 }
 
 #[tauri::command]
-pub async fn ssh_sftp_remove_dir(session_id: &str, dir: &str) -> Result<(), String> {
+pub async fn ssh_sftp_mkdir(session_id: &str, dir: &str) -> Result<(), String> {
     let sftp = ssh_get_sftp(session_id).await?;
-    sftp.remove_dir(dir).await.map_err(|e| e.to_string())
+    sftp.create_dir(dir).await.map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -4,9 +4,9 @@ use once_cell::sync::Lazy;
 use russh::client::Msg;
 use russh::keys::*;
 use russh::*;
-use russh_sftp::client::SftpSession;
+use russh_sftp::client::{RawSftpSession, SftpSession};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex as StdMutex};
 use std::time::Duration;
 use tokio::sync::Mutex;
@@ -19,13 +19,47 @@ static SSH_MAP: Lazy<Arc<StdMutex<HashMap<String, Arc<SshSession>>>>> =
 /// 链接Sftp会话（key: session_id）
 static SFTP_MAP: Lazy<Arc<StdMutex<HashMap<String, Arc<SftpSession>>>>> =
     Lazy::new(|| Arc::new(StdMutex::new(HashMap::new())));
+/// 原始Sftp会话，用于流水线传输与扩展请求（key: session_id）
+static RAW_SFTP_MAP: Lazy<Arc<StdMutex<HashMap<String, Arc<RawSftp>>>>> =
+    Lazy::new(|| Arc::new(StdMutex::new(HashMap::new())));
+/// Sftp子系统被服务端拒绝的会话（如 dropbear/busybox），此类会话改用 SCP 传输
+static SFTP_REFUSED: Lazy<Arc<StdMutex<HashSet<String>>>> =
+    Lazy::new(|| Arc::new(StdMutex::new(HashSet::new())));
 /// 配置管理（key: config_id）
 static CONFIG_MAP: Lazy<Arc<StdMutex<HashMap<String, SshConfig>>>> =
     Lazy::new(|| Arc::new(StdMutex::new(HashMap::new())));
-/// 存储主机键验证的响应通道（key: fingerprint）
-static HOST_KEY_CHANNEL: Lazy<Arc<StdMutex<HashMap<String, tokio::sync::oneshot::Sender<bool>>>>> =
+/// 存储等待用户确认的主机密钥和响应通道（key: session_id）
+static HOST_KEY_CHANNEL: Lazy<Arc<StdMutex<HashMap<String, PendingHostKey>>>> =
+    Lazy::new(|| Arc::new(StdMutex::new(HashMap::new())));
+/// 用户已信任的主机密钥指纹（key: (host, port)），无界面连接只接受该主机对应的密钥
+static TRUSTED_HOST_KEYS: Lazy<Arc<StdMutex<HashMap<(String, u16), HashSet<String>>>>> =
     Lazy::new(|| Arc::new(StdMutex::new(HashMap::new())));
 
+struct PendingHostKey {
+    fingerprint: String,
+    host: String,
+    port: u16,
+    tx: tokio::sync::oneshot::Sender<bool>,
+}
+
+/// 前端已确认的主机密钥
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrustedHostKey {
+    pub host: String,
+    pub port: u16,
+    pub fingerprint: String,
+}
+
+fn trust_host_key(host: String, port: u16, fingerprint: String) {
+    TRUSTED_HOST_KEYS
+        .lock()
+        .unwrap()
+        .entry((host, port))
+        .or_default()
+        .insert(fingerprint);
+}
+
 /// SSH/串口 连接配置
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -69,6 +103,9 @@ pub struct SshConfig {
     /// 端口转发配置列表（仅SSH）
     #[serde(default)]
     pub port_forwards: Vec<PortForwardConfig>,
+    /// 服务器监控配置（仅SSH）
+    #[serde(default)]
+    pub monitor: monitor::MonitorConfig,
     // === 串口专用字段 ===
     /// 串口设备名（如 "COM3" 或 "/dev/ttyUSB0"）
     #[serde(default)]
@@ -258,15 +295,32 @@ pub async fn ssh_connect(
     }
 }
 
-/// 响应用户对服务器主机密钥的确认/拒绝
+/// 响应用户对服务器主机密钥的确认/拒绝；未传 session_id 时按指纹查找等待中的连接
 #[tauri::command]
-pub async fn ssh_respond_host_key(fingerprint: &str, accept: bool) -> Result<(), String> {
-    let tx = {
+pub async fn ssh_respond_host_key(
+    session_id: Option<String>,
+    fingerprint: &str,
+    accept: bool,
+) -> Result<(), String> {
+    let pending = {
         let mut map = HOST_KEY_CHANNEL.lock().unwrap();
-        map.remove(fingerprint)
+        let session_id = session_id.or_else(|| {
+            map.iter()
+                .find(|(_, pending)| pending.fingerprint == fingerprint)
+                .map(|(session_id, _)| session_id.clone())
+        });
+        match session_id {
+            Some(session_id) if map.get(&session_id).is_some_and(|pending| pending.fingerprint == fingerprint) => {
+                map.remove(&session_id)
+            }
+            _ => None,
+        }
     };
-    if let Some(tx) = tx {
-        if tx.send(accept).is_err() {
+    if let Some(pending) = pending {
+        if accept {
+            trust_host_key(pending.host, pending.port, pending.fingerprint);
+        }
+        if pending.tx.send(accept).is_err() {
             return Err("Failed to send response, channel closed".to_string());
         }
         Ok(())
@@ -275,6 +329,14 @@ pub async fn ssh_respond_host_key(fingerprint: &str, accept: bool) -> Result<(),
     }
 }
 
+/// 同步前端已确认的主机密钥（主机、端口与指纹），供无界面连接校验
+#[tauri::command]
+pub fn sync_host_keys(host_keys: Vec<TrustedHostKey>) {
+    for key in host_keys {
+        trust_host_key(key.host, key.port, key.fingerprint);
+    }
+}
+
 #[tauri::command]
 pub async fn ssh_run_command(session_id: &str, command: &str) -> Result<(), String> {
     let sess: Option<Arc<SshSession>> = {
@@ -290,6 +352,93 @@ pub async fn ssh_run_command(session_id: &str, command: &str) -> Result<(), Stri
     }
 }
 
+/// 按配置ID获取已同步的连接配置
+pub fn ssh_get_config(config_id: &str) -> Option<SshConfig> {
+    CONFIG_MAP.lock().unwrap().get(config_id).cloned()
+}
+
+/// 只建立 SSH 连接、不打开终端（用于后台监控等无界面任务），配置了跳板机时逐层连接
+/// 不弹出主机密钥确认，未信任的密钥直接连接失败
+pub async fn ssh_connect_headless(
+    app: AppHandle,
+    config: &SshConfig,
+    session_id: String,
+) -> Result<Arc<client::Handle<SshClient>>, String> {
+    let bastion_config = match non_empty(config.bastion_config_id.as_ref()) {
+        Some(bastion_id) => Some(
+            ssh_get_config(bastion_id).ok_or_else(|| format!("Bastion config not found: {}", bastion_id))?,
+        ),
+        None => None,
+    };
+    let handle = async {
+        match bastion_config {
+            Some(bastion_config) => {
+                let bastion_handle =
+                    SshSession::connect_bastion_chain(app.clone(), bastion_config, session_id.clone(), false).await?;
+                let channel = bastion_handle
+                    .channel_open_direct_tcpip(&config.host, config.port as u32, "127.0.0.1", 0)
+                    .await?;
+                SshSession::connect_base(app, config, Some(channel.into_stream()), session_id, false).await
+            }
+            None => SshSession::connect_base(app, config, None, session_id, false).await,
+        }
+    }
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(Arc::new(handle))
+}
+
+/// 断开 ssh_connect_headless 建立的连接
+pub async fn ssh_disconnect_handle(handle: &client::Handle<SshClient>) {
+    let _ = handle
+        .disconnect(Disconnect::ByApplication, "user close", "en")
+        .await;
+}
+
+/// 服务端不提供 sftp 子系统时返回的错误
+pub const SFTP_UNAVAILABLE: &str = "SFTP subsystem not available";
+
+/// 获取SSH连接句柄，用于打开 exec 等旁路通道
+pub fn ssh_get_handle(session_id: &str) -> Result<Arc<client::Handle<SshClient>>, String> {
+    let map = SSH_MAP.lock().unwrap();
+    match map.get(session_id) {
+        Some(sess) => Ok(sess.handle.clone()),
+        None => Err("Session not found".to_string()),
+    }
+}
+
+/// 该会话的 sftp 子系统是否已被服务端拒绝
+pub fn ssh_sftp_refused(session_id: &str) -> bool {
+    SFTP_REFUSED.lock().unwrap().contains(session_id)
+}
+
+/// 请求 sftp 子系统并等待服务端答复，被拒绝时记录到 SFTP_REFUSED
+async fn request_sftp_subsystem(
+    session_id: &str,
+    handle: &client::Handle<SshClient>,
+) -> Result<Channel<Msg>, String> {
+    if ssh_sftp_refused(session_id) {
+        return Err(SFTP_UNAVAILABLE.to_string());
+    }
+    let mut channel = handle.channel_open_session().await.map_err(|e| e.to_string())?;
+    channel
+        .request_subsystem(true, "sftp")
+        .await
+        .map_err(|e| e.to_string())?;
+    loop {
+        match channel.wait().await {
+            Some(ChannelMsg::Success) => return Ok(channel),
+            Some(ChannelMsg::Failure) | Some(ChannelMsg::Eof) | Some(ChannelMsg::Close) | None => {
+                info!("SFTP subsystem refused for session: {}", session_id);
+                SFTP_REFUSED.lock().unwrap().insert(session_id.to_string());
+                let _ = channel.close().await;
+                return Err(SFTP_UNAVAILABLE.to_string());
+            }
+            _ => {}
+        }
+    }
+}
+
 pub async fn ssh_get_sftp(session_id: &str) -> Result<Arc<SftpSession>, String> {
     let sess: Option<Arc<SshSession>> = {
         let map = SSH_MAP.lock().unwrap();
@@ -304,9 +453,10 @@ pub async fn ssh_get_sftp(session_id: &str) -> Result<Arc<SftpSession>, String>
             match sftp_session {
                 Some(sftp) => Ok(sftp),
                 None => {
-                    let channel = sess.handle.channel_open_session().await.unwrap();
-                    channel.request_subsystem(true, "sftp").await.unwrap();
-                    let sftp = SftpSession::new(channel.into_stream()).await.unwrap();
+                    let channel = request_sftp_subsystem(session_id, &sess.handle).await?;
+                    let sftp = SftpSession::new(channel.into_stream())
+                        .await
+                        .map_err(|e| e.to_string())?;
                     let sftp = Arc::new(sftp);
                     SFTP_MAP
                         .lock()
@@ -320,6 +470,43 @@ pub async fn ssh_get_sftp(session_id: &str) -> Result<Arc<SftpSession>, String>
     }
 }
 
+/// 原始Sftp会话及服务端在 VERSION 中声明的扩展
+pub struct RawSftp {
+    pub session: RawSftpSession,
+    pub extensions: HashMap<String, String>,
+}
+
+/// 获取原始Sftp会话（独立的 sftp 子系统通道，可并发发送多个请求）
+pub async fn ssh_get_raw_sftp(session_id: &str) -> Result<Arc<RawSftp>, String> {
+    let sess: Option<Arc<SshSession>> = {
+        let map = SSH_MAP.lock().unwrap();
+        map.get(session_id).cloned()
+    };
+    let Some(sess) = sess else {
+        return Err("Session not found".to_string());
+    };
+    let raw_session: Option<Arc<RawSftp>> = {
+        let map = RAW_SFTP_MAP.lock().unwrap();
+        map.get(session_id).cloned()
+    };
+    if let Some(raw) = raw_session {
+        return Ok(raw);
+    }
+
+    let channel = request_sftp_subsystem(session_id, &sess.handle).await?;
+    let session = RawSftpSession::new(channel.into_stream());
+    let version = session.init().await.map_err(|e| e.to_string())?;
+    let raw = Arc::new(RawSftp {
+        session,
+        extensions: version.extensions,
+    });
+    RAW_SFTP_MAP
+        .lock()
+        .unwrap()
+        .insert(session_id.to_string(), raw.clone());
+    Ok(raw)
+}
+
 #[tauri::command]
 pub async fn ssh_window_change(
     session_id: &str,
@@ -410,6 +597,17 @@ pub async fn ssh_close(session_id: &str) -> Result<(), String> {
     if let Some(sess) = sess {
         // 关闭sftp链接
         SFTP_MAP.lock().unwrap().remove(session_id);
+        RAW_SFTP_MAP.lock().unwrap().remove(session_id);
+        SFTP_REFUSED.lock().unwrap().remove(session_id);
+        // 结束目录监听
+        crate::watch::close_session_watches(session_id).await;
+        crate::tail::close_session_tails(session_id).await;
+        crate::docker::close_session_logs(session_id).await;
+        // 关闭监控通道
+        crate::monitor::stop_session_monitor(session_id).await;
+        // 结束外部编辑并清理本地临时文件
+        #[cfg(desktop)]
+        crate::editor::close_session_edits(session_id).await;
         // 断开链接
         sess.close().await.map_err(|e| e.to_string())?;
     }
@@ -429,6 +627,11 @@ pub fn sync_config(config_map: HashMap<String, SshConfig>) {
 pub struct SshClient {
     app: AppHandle,
     session_id: String,
+    /// 目标主机与端口（经跳板机连接时为最终目标），主机密钥按此记录与校验
+    host: String,
+    port: u16,
+    /// 为 false 时不询问用户，只接受已信任的主机密钥
+    interactive: bool,
 }
 
 impl client::Handler for SshClient {
@@ -450,18 +653,44 @@ impl client::Handler for SshClient {
 
         info!("check_server_key: fingerprint={} key_type={} session={}", fingerprint, key_type_str, self.session_id);
 
+        if !self.interactive {
+            let trusted = TRUSTED_HOST_KEYS
+                .lock()
+                .unwrap()
+                .get(&(self.host.clone(), self.port))
+                .is_some_and(|keys| keys.contains(&fingerprint));
+            if trusted {
+                return Ok(true);
+            }
+            error!(
+                "Untrusted host key {} for {}:{} (headless session {})",
+                fingerprint, self.host, self.port, self.session_id
+            );
+            return Ok(false);
+        }
+
         // 发送主机密钥验证事件到前端
         let _ = self.app.emit("ssh_host_key", SshHostKeyPayload {
             session_id: self.session_id.clone(),
             fingerprint: fingerprint.clone(),
             key_type: key_type_str.clone(),
+            host: self.host.clone(),
+            port: self.port,
         });
 
         // 创建 oneshot 通道等待用户响应
         let (tx, rx) = tokio::sync::oneshot::channel::<bool>();
         {
             let mut map = HOST_KEY_CHANNEL.lock().unwrap();
-            map.insert(fingerprint.clone(), tx);
+            map.insert(
+                self.session_id.clone(),
+                PendingHostKey {
+                    fingerprint: fingerprint.clone(),
+                    host: self.host.clone(),
+                    port: self.port,
+                    tx,
+                },
+            );
         }
 
         // 等待用户响应（带超时）
@@ -489,7 +718,7 @@ impl client::Handler for SshClient {
         // 清理：无论成功、拒绝还是超时，都移除通道避免内存泄漏
         {
             let mut map = HOST_KEY_CHANNEL.lock().unwrap();
-            map.remove(&fingerprint);
+            map.remove(&self.session_id);
         }
 
         result
@@ -502,6 +731,8 @@ struct SshHostKeyPayload {
     session_id: String,
     fingerprint: String,
     key_type: String,
+    host: String,
+    port: u16,
 }
 
 #[derive(Clone, serde::Serialize)]
@@ -526,8 +757,6 @@ pub struct SshSession {
 
     shutdown_rx: tokio::sync::watch::Receiver<bool>,
     shutdown_tx: tokio::sync::watch::Sender<bool>,
-    /// 监控通道关闭信号
-    monitor_shutdown_tx: Option<tokio::sync::watch::Sender<bool>>,
 }
 
 /// 端口转发条目
@@ -547,6 +776,7 @@ impl SshSession {
         config: &SshConfig,
         stream_opt: Option<ChannelStream<Msg>>,
         session_id: String,
+        interactive: bool,
     ) -> Result<client::Handle<SshClient>> {
         let client_config = client::Config {
             keepalive_interval: Some(Duration::from_secs(config.keepalive_interval)),
@@ -556,6 +786,9 @@ impl SshSession {
         let sh = SshClient {
             app,
             session_id,
+            host: config.host.clone(),
+            port: config.port,
+            interactive,
         };
         let mut handle = if let Some(stream) = stream_opt {
             client::connect_stream(Arc::new(client_config), stream, sh).await?
@@ -615,7 +848,7 @@ impl SshSession {
         rows: u32,
         on_event: tauri::ipc::Channel<SshChannelEvent>,
     ) -> Result<Self> {
-        let handle = Self::connect_base(app.clone(), config, None, session_id.clone()).await?;
+        let handle = Self::connect_base(app.clone(), config, None, session_id.clone(), true).await?;
         let channel = handle.channel_open_session().await?;
         channel
             .request_pty(true, "xterm", cols, rows, 0, 0, &[])
@@ -624,8 +857,6 @@ impl SshSession {
 
         let (read, write) = channel.split();
         let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
-        // 监控专用 shutdown channel
-        let (monitor_tx, monitor_rx) = tokio::sync::watch::channel(false);
         let session = Self {
             session_id: session_id.clone(),
             config_id,
@@ -635,17 +866,17 @@ impl SshSession {
             port_forward_id_counter: std::sync::atomic::AtomicU32::new(1),
             shutdown_rx,
             shutdown_tx,
-            monitor_shutdown_tx: Some(monitor_tx),
         };
 
         session.init_shell(app.clone(), read, on_event).await;
-        // 启动监控通道
-        let monitor_handle_clone = session.handle.clone();
-        let monitor_session_id = session_id.clone();
-        let monitor_app = app.clone();
-        tokio::spawn(async move {
-            monitor::start_monitor(monitor_app, monitor_handle_clone, monitor_session_id, monitor_rx).await;
-        });
+        // 按配置启动监控通道
+        monitor::spawn_monitor(
+            app.clone(),
+            session.handle.clone(),
+            session_id.clone(),
+            config.monitor.clone(),
+        )
+        .await;
 
         Ok(session)
     }
@@ -655,6 +886,7 @@ impl SshSession {
         app: AppHandle,
         bastion_config: SshConfig,
         session_id: String,
+        interactive: bool,
     ) -> anyhow::Result<client::Handle<SshClient>> {
         let mut chain = Vec::new();
         let mut current = bastion_config.bastion_config_id.clone();
@@ -686,7 +918,7 @@ impl SshSession {
             let channel = match parent_client {
                 None => {
                     // 1. 登录最后一层 bastion
-                    Self::connect_base(app.clone(), &cfg, None, session_id.clone()).await?
+                    Self::connect_base(app.clone(), &cfg, None, session_id.clone(), interactive).await?
                 }
                 Some(bastion_handle) => {
                     // 2. 从外层 bastion → 上一层 bastion
@@ -698,7 +930,7 @@ impl SshSession {
                             0,
                         )
                         .await?;
-                    Self::connect_base(app.clone(), &cfg, Some(stream.into_stream()), session_id.clone()).await?
+                    Self::connect_base(app.clone(), &cfg, Some(stream.into_stream()), session_id.clone(), interactive).await?
                 }
             };
             parent_client = Option::from(channel);
@@ -722,7 +954,7 @@ impl SshSession {
         info!("Connect via bastion: {:?}", bastion_config);
         // 先建立跳板机会话(循环调用以实现多层叠甲)
         let bastion_handle =
-            Self::connect_bastion_chain(app.clone(), bastion_config.to_owned(), session_id.clone()).await?;
+            Self::connect_bastion_chain(app.clone(), bastion_config.to_owned(), session_id.clone(), true).await?;
 
         // 创建到目标机的通道
         let channel = bastion_handle
@@ -732,7 +964,7 @@ impl SshSession {
 
         // 建立目标链接
         let handle: russh::client::Handle<SshClient> =
-            Self::connect_base(app.clone(), config, Some(stream), session_id.clone()).await?;
+            Self::connect_base(app.clone(), config, Some(stream), session_id.clone(), true).await?;
         let channel = handle.channel_open_session().await?;
         // 在通道上请求 PTY 和 shell
         channel
@@ -742,8 +974,6 @@ impl SshSession {
 
         let (read, write) = channel.split();
         let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
-        // 监控专用 shutdown channel
-        let (monitor_tx, monitor_rx) = tokio::sync::watch::channel(false);
         let session = Self {
             session_id: session_id.clone(),
             config_id,
@@ -753,16 +983,16 @@ impl SshSession {
             port_forward_id_counter: std::sync::atomic::AtomicU32::new(1),
             shutdown_rx,
             shutdown_tx,
-            monitor_shutdown_tx: Some(monitor_tx),
         };
         session.init_shell(app.clone(), read, on_event).await;
-        // 启动监控通道
-        let monitor_handle_clone = session.handle.clone();
-        let monitor_session_id = session_id.clone();
-        let monitor_app = app.clone();
-        tokio::spawn(async move {
-            monitor::start_monitor(monitor_app, monitor_handle_clone, monitor_session_id, monitor_rx).await;
-        });
+        // 按配置启动监控通道
+        monitor::spawn_monitor(
+            app.clone(),
+            session.handle.clone(),
+            session_id.clone(),
+            config.monitor.clone(),
+        )
+        .await;
         Ok(session)
     }
 
@@ -1205,10 +1435,6 @@ impl SshSession {
     pub async fn close(&self) -> Result<()> {
         // 先发送关闭信号，阻止新的操作
         let _ = self.shutdown_tx.send(true);
-        // 关闭监控通道
-        if let Some(ref tx) = self.monitor_shutdown_tx {
-            let _ = tx.send(true);
-        }
         let channel = {
             let mut guard = self.write.lock().await;
             guard.take()
@@ -1222,7 +1448,7 @@ impl SshSession {
         Ok(())
     }
 
-    // 启动服务器状态监控通道（由 connect_direct/connect_via_bastion 调用 monitor::start_monitor）
+    // 启动服务器状态监控通道（由 connect_direct/connect_via_bastion 调用 monitor::spawn_monitor）
 }
 
 /// SOCKS5 握手：从客户端读取请求并返回目标地址
@@ -0,0 +1,538 @@
+use crate::checksum::{local_file_hash, remote_file_hash};
+use crate::search::build_excludes;
+use crate::sftp::{remote_join, sftp_download_file, sftp_upload_file};
+use crate::transfer::{percent, register_task, transfer_speed, TransferTasks};
+use crate::ssh::{ssh_get_raw_sftp, ssh_get_sftp};
+use log::info;
+use once_cell::sync::Lazy;
+use russh_sftp::client::SftpSession;
+use russh_sftp::protocol::FileAttributes;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+
+/// 同步任务（key: task_id）
+static SYNC_TASKS: Lazy<TransferTasks> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 修改时间比较的容差（秒），兼容 FAT 等低精度文件系统
+const MTIME_TOLERANCE: u64 = 2;
+
+/// 同步方向
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SyncMode {
+    /// 双向：只在一边存在的复制到另一边，两边不同的以较新的为准
+    /// 没有上次同步的记录，无法区分"一边新增"和"另一边删除"，因此双向模式从不删除；
+    /// 类型不一致或修改时间相同而内容不同的项作为冲突交给用户决定
+    TwoWay,
+    /// 本地 → 远端（镜像）
+    Upload,
+    /// 远端 → 本地（镜像）
+    Download,
+}
+
+/// 同步选项
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncOptions {
+    pub mode: SyncMode,
+    /// 排除的文件/目录（glob，匹配名称或相对路径）
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// 使用 SHA-256 比较内容（否则只比较大小和修改时间）
+    #[serde(default)]
+    pub checksum: bool,
+    /// 镜像模式下删除目标端多余的文件
+    #[serde(default)]
+    pub delete: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SyncActionKind {
+    Upload,
+    Download,
+    DeleteRemote,
+    DeleteLocal,
+    /// 无法自动判断，执行时跳过
+    Conflict,
+}
+
+/// 同步计划中的一项
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncAction {
+    pub kind: SyncActionKind,
+    /// 相对路径（以 / 分隔）
+    pub path: String,
+    pub is_dir: bool,
+    pub size: u64,
+    /// 生成该项的原因，供预览显示
+    #[serde(default)]
+    pub reason: String,
+}
+
+/// 同步计划
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncPlan {
+    pub actions: Vec<SyncAction>,
+    pub local_files: u32,
+    pub remote_files: u32,
+    /// 需要传输的总字节数
+    pub transfer_bytes: u64,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase",
+    tag = "event",
+    content = "data"
+)]
+pub enum SyncEvent {
+    /// 开始执行某一项
+    Action {
+        index: u32,
+        kind: SyncActionKind,
+        path: String,
+    },
+    Process {
+        val: u32,
+        transferred: u64,
+        total: u64,
+        speed: f64,
+    },
+    /// 某一项执行失败，继续执行其余项
+    ActionError {
+        path: String,
+        message: String,
+    },
+    Finished {
+        completed: u32,
+        failed: u32,
+        transferred: u64,
+    },
+    Cancelled,
+}
+
+/// 目录树中的一项
+#[derive(Clone, Debug)]
+struct SyncEntry {
+    is_dir: bool,
+    size: u64,
+    mtime: u64,
+}
+
+/// 比较本地与远端目录，生成同步计划供预览
+#[tauri::command]
+pub async fn ssh_sync_plan(
+    session_id: &str,
+    local_dir: &str,
+    remote_dir: &str,
+    options: SyncOptions,
+) -> Result<SyncPlan, String> {
+    let sftp = ssh_get_sftp(session_id).await?;
+    let excludes = build_excludes(&options.exclude)?;
+
+    let local_root = PathBuf::from(local_dir);
+    let local = {
+        let local_root = local_root.clone();
+        let excludes = excludes.clone();
+        tokio::task::spawn_blocking(move || list_local(&local_root, &excludes))
+            .await
+            .map_err(|e| e.to_string())??
+    };
+    let remote = list_remote(&sftp, remote_dir, &excludes).await?;
+
+    // 一边是目录、另一边是文件时整体作为冲突，其下的子项不再单独处理
+    let mismatched: Vec<&str> = local
+        .iter()
+        .filter(|(path, l)| remote.get(*path).is_some_and(|r| r.is_dir != l.is_dir))
+        .map(|(path, _)| path.as_str())
+        .collect();
+    let under_mismatch = |path: &str| {
+        mismatched
+            .iter()
+            .any(|dir| path.strip_prefix(dir).is_some_and(|rest| rest.starts_with('/')))
+    };
+
+    let mut actions = Vec::new();
+    let mut push = |kind, path: &str, entry: &SyncEntry, reason: &str| {
+        actions.push(SyncAction {
+            kind,
+            path: path.to_string(),
+            is_dir: entry.is_dir,
+            size: if entry.is_dir { 0 } else { entry.size },
+            reason: reason.to_string(),
+        });
+    };
+
+    for (path, l) in &local {
+        if under_mismatch(path) {
+            continue;
+        }
+        match remote.get(path) {
+            None => match options.mode {
+                SyncMode::TwoWay | SyncMode::Upload => push(SyncActionKind::Upload, path, l, "onlyLocal"),
+                SyncMode::Download if options.delete => push(SyncActionKind::DeleteLocal, path, l, "onlyLocal"),
+                SyncMode::Download => {}
+            },
+            Some(r) if l.is_dir != r.is_dir => push(SyncActionKind::Conflict, path, l, "typeMismatch"),
+            Some(_) if l.is_dir => {}
+            Some(r) => {
+                let differs = if options.checksum && l.size == r.size {
+                    let local_path = local_path_of(&local_root, path);
+                    let (local_hash, _) = local_file_hash(&local_path, &ring::digest::SHA256).await?;
+                    let remote_hash =
+                        remote_file_hash(&sftp, &remote_join(remote_dir, path), &ring::digest::SHA256).await?;
+                    local_hash != remote_hash
+                } else {
+                    l.size != r.size || l.mtime.abs_diff(r.mtime) > MTIME_TOLERANCE
+                };
+                if !differs {
+                    continue;
+                }
+                match options.mode {
+                    SyncMode::Upload => push(SyncActionKind::Upload, path, l, "changed"),
+                    SyncMode::Download => push(SyncActionKind::Download, path, r, "changed"),
+                    SyncMode::TwoWay if l.mtime.abs_diff(r.mtime) <= MTIME_TOLERANCE => {
+                        push(SyncActionKind::Conflict, path, l, "sameMtime")
+                    }
+                    SyncMode::TwoWay if l.mtime > r.mtime => push(SyncActionKind::Upload, path, l, "localNewer"),
+                    SyncMode::TwoWay => push(SyncActionKind::Download, path, r, "remoteNewer"),
+                }
+            }
+        }
+    }
+    for (path, r) in &remote {
+        if local.contains_key(path) || under_mismatch(path) {
+            continue;
+        }
+        match options.mode {
+            SyncMode::TwoWay | SyncMode::Download => push(SyncActionKind::Download, path, r, "onlyRemote"),
+            SyncMode::Upload if options.delete => push(SyncActionKind::DeleteRemote, path, r, "onlyRemote"),
+            SyncMode::Upload => {}
+        }
+    }
+
+    let transfer_bytes = actions
+        .iter()
+        .filter(|a| matches!(a.kind, SyncActionKind::Upload | SyncActionKind::Download))
+        .map(|a| a.size)
+        .sum();
+    Ok(SyncPlan {
+        actions,
+        local_files: local.values().filter(|e| !e.is_dir).count() as u32,
+        remote_files: remote.values().filter(|e| !e.is_dir).count() as u32,
+        transfer_bytes,
+    })
+}
+
+/// 执行（可能经用户筛选、修改后的）同步计划，冲突项会被跳过
+#[tauri::command]
+pub async fn ssh_sync_execute(
+    task_id: &str,
+    session_id: &str,
+    local_dir: &str,
+    remote_dir: &str,
+    actions: Vec<SyncAction>,
+    on_sync_event: tauri::ipc::Channel<SyncEvent>,
+) -> Result<u32, String> {
+    for action in &actions {
+        check_rel_path(&action.path)?;
+    }
+    let sftp = ssh_get_sftp(session_id).await?;
+    let raw = ssh_get_raw_sftp(session_id).await?;
+    let token = register_task(&SYNC_TASKS, task_id).await;
+
+    // 执行顺序：先建目录（父目录在前），再传文件，最后删除（子项在前）
+    let mut ordered: Vec<(usize, &SyncAction)> = actions.iter().enumerate().collect();
+    ordered.sort_by_key(|(_, a)| {
+        let depth = a.path.matches('/').count() as i64;
+        match (a.kind, a.is_dir) {
+            (SyncActionKind::Upload | SyncActionKind::Download, true) => (0, depth),
+            (SyncActionKind::Upload | SyncActionKind::Download, false) => (1, 0),
+            (_, false) => (2, 0),
+            (_, true) => (3, -depth),
+        }
+    });
+
+    let total: u64 = actions
+        .iter()
+        .filter(|a| !a.is_dir && matches!(a.kind, SyncActionKind::Upload | SyncActionKind::Download))
+        .map(|a| a.size)
+        .sum();
+    let local_root = PathBuf::from(local_dir);
+    let started = Instant::now();
+    let mut transferred: u64 = 0;
+    let mut completed: u32 = 0;
+    let mut failed: u32 = 0;
+    let mut last_progress = u32::MAX;
+    let mut last_emit = Instant::now();
+
+    for (index, action) in ordered {
+        if token.is_cancelled() {
+            break;
+        }
+        if action.kind == SyncActionKind::Conflict {
+            continue;
+        }
+        let _ = on_sync_event.send(SyncEvent::Action {
+            index: index as u32,
+            kind: action.kind,
+            path: action.path.clone(),
+        });
+
+        let local_path = local_path_of(&local_root, &action.path);
+        let remote_path = remote_join(remote_dir, &action.path);
+        let base = transferred;
+        let mut on_progress = |done: u64, _size: u64| {
+            let current = base + done;
+            let val = percent(current, total);
+            if val != last_progress || last_emit.elapsed() >= Duration::from_millis(500) {
+                last_progress = val;
+                last_emit = Instant::now();
+                let _ = on_sync_event.send(SyncEvent::Process {
+                    val,
+                    transferred: current,
+                    total,
+                    speed: transfer_speed(current, started),
+                });
+            }
+        };
+
+        let result: Result<u64, String> = match (action.kind, action.is_dir) {
+            (SyncActionKind::Upload, true) => match sftp.try_exists(remote_path.as_str()).await {
+                Ok(true) => Ok(0),
+                _ => sftp.create_dir(remote_path.as_str()).await.map(|_| 0).map_err(|e| e.to_string()),
+            },
+            (SyncActionKind::Download, true) => tokio::fs::create_dir_all(&local_path)
+                .await
+                .map(|_| 0)
+                .map_err(|e| e.to_string()),
+            (SyncActionKind::Upload, false) => {
+                match sftp_upload_file(&raw, &local_path, &remote_path, &token, &mut on_progress).await {
+                    Ok(n) => {
+                        // 远端修改时间与本地保持一致，下次比较时视为相同
+                        let mtime = local_mtime(&local_path);
+                        let attrs = FileAttributes {
+                            atime: Some(mtime as u32),
+                            mtime: Some(mtime as u32),
+                            ..FileAttributes::empty()
+                        };
+                        let _ = sftp.set_metadata(remote_path.as_str(), attrs).await;
+                        Ok(n)
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+            (SyncActionKind::Download, false) => {
+                if let Some(parent) = local_path.parent() {
+                    let _ = tokio::fs::create_dir_all(parent).await;
+                }
+                match sftp_download_file(&sftp, &remote_path, &local_path, &token, &mut on_progress).await {
+                    Ok(n) => {
+                        if let Ok(meta) = sftp.metadata(remote_path.as_str()).await {
+                            set_local_mtime(&local_path, meta.mtime.unwrap_or(0) as u64);
+                        }
+                        Ok(n)
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+            (SyncActionKind::DeleteRemote, true) => {
+                sftp.remove_dir(remote_path.as_str()).await.map(|_| 0).map_err(|e| e.to_string())
+            }
+            (SyncActionKind::DeleteRemote, false) => {
+                sftp.remove_file(remote_path.as_str()).await.map(|_| 0).map_err(|e| e.to_string())
+            }
+            (SyncActionKind::DeleteLocal, true) => tokio::fs::remove_dir(&local_path)
+                .await
+                .map(|_| 0)
+                .map_err(|e| e.to_string()),
+            (SyncActionKind::DeleteLocal, false) => tokio::fs::remove_file(&local_path)
+                .await
+                .map(|_| 0)
+                .map_err(|e| e.to_string()),
+            (SyncActionKind::Conflict, _) => Ok(0),
+        };
+
+        match result {
+            Ok(n) => {
+                transferred += n;
+                completed += 1;
+            }
+            Err(_) if token.is_cancelled() => break,
+            Err(message) => {
+                failed += 1;
+                let _ = on_sync_event.send(SyncEvent::ActionError {
+                    path: action.path.clone(),
+                    message,
+                });
+            }
+        }
+    }
+
+    SYNC_TASKS.lock().await.remove(task_id);
+    if token.is_cancelled() {
+        let _ = on_sync_event.send(SyncEvent::Cancelled);
+        return Err("同步已取消".to_string());
+    }
+    info!(
+        "Sync finished {} <-> {}: {} completed, {} failed",
+        local_dir, remote_dir, completed, failed
+    );
+    let _ = on_sync_event.send(SyncEvent::Finished {
+        completed,
+        failed,
+        transferred,
+    });
+    Ok(completed)
+}
+
+#[tauri::command]
+pub async fn ssh_sync_cancel(task_id: String) -> Result<(), String> {
+    let mut map = SYNC_TASKS.lock().await;
+
+    if let Some(token) = map.remove(&task_id) {
+        token.cancel();
+        Ok(())
+    } else {
+        Err("任务不存在".to_string())
+    }
+}
+
+fn is_excluded(excludes: &[glob::Pattern], name: &str, rel_path: &str) -> bool {
+    excludes.iter().any(|p| p.matches(name) || p.matches(rel_path))
+}
+
+fn join_rel(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", parent, name)
+    }
+}
+
+/// 计划来自前端，路径必须是同步根目录下的相对路径：
+/// 不允许绝对路径、盘符、`.` 和 `..`，避免读写或删除根目录以外的文件
+fn check_rel_path(rel_path: &str) -> Result<(), String> {
+    let valid = rel_path.split('/').all(|part| {
+        let mut components = Path::new(part).components();
+        matches!(components.next(), Some(Component::Normal(_))) && components.next().is_none()
+    });
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid sync path: {}", rel_path))
+    }
+}
+
+/// 相对路径转换为本地路径
+fn local_path_of(root: &Path, rel_path: &str) -> PathBuf {
+    let mut path = root.to_path_buf();
+    for part in rel_path.split('/').filter(|p| !p.is_empty()) {
+        path.push(part);
+    }
+    path
+}
+
+fn local_mtime(path: &Path) -> u64 {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn set_local_mtime(path: &Path, mtime: u64) {
+    let time = UNIX_EPOCH + Duration::from_secs(mtime);
+    if let Ok(file) = std::fs::File::options().write(true).open(path) {
+        let _ = file.set_modified(time);
+    }
+}
+
+/// 遍历本地目录（不跟随符号链接）
+fn list_local(root: &Path, excludes: &[glob::Pattern]) -> Result<BTreeMap<String, SyncEntry>, String> {
+    let mut result = BTreeMap::new();
+    if !root.exists() {
+        return Ok(result);
+    }
+    let mut stack: Vec<String> = vec![String::new()];
+    while let Some(rel_dir) = stack.pop() {
+        let entries = std::fs::read_dir(local_path_of(root, &rel_dir)).map_err(|e| e.to_string())?;
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let rel_path = join_rel(&rel_dir, &name);
+            if is_excluded(excludes, &name, &rel_path) {
+                continue;
+            }
+            let Ok(meta) = std::fs::symlink_metadata(entry.path()) else {
+                continue;
+            };
+            if meta.file_type().is_symlink() {
+                continue;
+            }
+            let mtime = meta
+                .modified()
+                .unwrap_or(SystemTime::UNIX_EPOCH)
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            if meta.is_dir() {
+                stack.push(rel_path.clone());
+            }
+            result.insert(rel_path, SyncEntry {
+                is_dir: meta.is_dir(),
+                size: meta.len(),
+                mtime,
+            });
+        }
+    }
+    Ok(result)
+}
+
+/// 遍历远端目录（不跟随符号链接），目录不存在时视为空
+async fn list_remote(
+    sftp: &SftpSession,
+    root: &str,
+    excludes: &[glob::Pattern],
+) -> Result<BTreeMap<String, SyncEntry>, String> {
+    let mut result = BTreeMap::new();
+    if !sftp.try_exists(root).await.map_err(|e| e.to_string())? {
+        return Ok(result);
+    }
+    let mut queue: VecDeque<String> = VecDeque::new();
+    queue.push_back(String::new());
+    while let Some(rel_dir) = queue.pop_front() {
+        let entries = sftp
+            .read_dir(remote_join(root, &rel_dir))
+            .await
+            .map_err(|e| format!("{}: {}", remote_join(root, &rel_dir), e))?;
+        for entry in entries {
+            let name = entry.file_name();
+            let rel_path = join_rel(&rel_dir, &name);
+            if is_excluded(excludes, &name, &rel_path) {
+                continue;
+            }
+            let metadata = entry.metadata();
+            let file_type = metadata.file_type();
+            if !file_type.is_dir() && !file_type.is_file() {
+                continue;
+            }
+            if file_type.is_dir() {
+                queue.push_back(rel_path.clone());
+            }
+            result.insert(rel_path, SyncEntry {
+                is_dir: file_type.is_dir(),
+                size: metadata.size.unwrap_or(0),
+                mtime: metadata.mtime.unwrap_or(0) as u64,
+            });
+        }
+    }
+    Ok(result)
+}
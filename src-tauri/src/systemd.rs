@@ -0,0 +1,308 @@
+use crate::exec::{exec_channel, exec_output, shell_quote, wait_output, ExecOutput};
+use log::info;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// 等待用户输入 sudo 密码的超时时间
+const SUDO_PROMPT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// sudo 密码请求的响应通道，None 表示用户取消
+type SudoResponder = tokio::sync::oneshot::Sender<Option<String>>;
+
+/// 存储 sudo 密码请求的响应通道（key: request_id）
+static SUDO_PROMPTS: Lazy<Arc<StdMutex<HashMap<String, SudoResponder>>>> =
+    Lazy::new(|| Arc::new(StdMutex::new(HashMap::new())));
+static SUDO_PROMPT_ID: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Clone, Debug, serde::Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemdUnit {
+    pub unit: String,
+    /// loaded、not-found 等
+    pub load: String,
+    /// active、inactive、failed 等
+    pub active: String,
+    /// running、exited、dead 等
+    pub sub: String,
+    pub description: String,
+    /// enabled、disabled、static 等（来自 list-unit-files，取不到时为空）
+    #[serde(default)]
+    pub unit_file_state: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemdUnitStatus {
+    pub unit: String,
+    /// systemctl show 的属性，如 ActiveState、MainPID、ExecMainStartTimestamp
+    pub properties: HashMap<String, String>,
+    /// 最近的日志行（journalctl -o short-iso）
+    pub journal: Vec<String>,
+    /// 无权限读取日志等情况下的错误信息
+    pub journal_error: Option<String>,
+}
+
+/// sudo 密码请求事件（全局事件 ssh_sudo_prompt），前端通过 ssh_sudo_respond 回复
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SudoPromptPayload {
+    request_id: String,
+    session_id: String,
+    /// 需要提权执行的命令，用于提示用户
+    command: String,
+    /// 上一次输入的密码错误
+    retry: bool,
+}
+
+const SHOW_PROPERTIES: &str = "Id,Description,LoadState,ActiveState,SubState,UnitFileState,\
+MainPID,ExecMainStartTimestamp,ActiveEnterTimestamp,MemoryCurrent,CPUUsageNSec,TasksCurrent,\
+NRestarts,Result,FragmentPath";
+
+/// 单元名只允许 systemd 接受的字符，避免被当作选项解析
+fn check_unit(unit: &str) -> Result<(), String> {
+    let valid = unit.chars().next().is_some_and(|c| c.is_ascii_alphanumeric())
+        && unit
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, ':' | '_' | '.' | '@' | '-' | '\\'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid unit name: {}", unit))
+    }
+}
+
+/// 列出 systemd 单元；unit_type 默认 service，all 为 true 时包含未激活的单元
+#[tauri::command]
+pub async fn ssh_systemd_list_units(
+    session_id: String,
+    unit_type: Option<String>,
+    all: bool,
+) -> Result<Vec<SystemdUnit>, String> {
+    let unit_type = unit_type.unwrap_or_else(|| "service".to_string());
+    if !unit_type.chars().all(|c| c.is_ascii_lowercase()) {
+        return Err(format!("Invalid unit type: {}", unit_type));
+    }
+    let all = if all { " --all" } else { "" };
+
+    // systemd 240 之前不支持 --output=json，回退到纯文本
+    let json = exec_output(
+        &session_id,
+        &format!("systemctl list-units --type={}{} --no-pager --output=json", unit_type, all),
+    )
+    .await?;
+    let mut units = match serde_json::from_slice::<Vec<SystemdUnit>>(&json.stdout) {
+        Ok(units) if json.success() => units,
+        _ => {
+            let output = exec_output(
+                &session_id,
+                &format!(
+                    "systemctl list-units --type={}{} --no-pager --no-legend --plain",
+                    unit_type, all
+                ),
+            )
+            .await?;
+            if !output.success() {
+                return Err(output.error_message());
+            }
+            parse_list_units(&output.stdout_text())
+        }
+    };
+
+    let files = exec_output(
+        &session_id,
+        &format!("systemctl list-unit-files --type={} --no-pager --no-legend", unit_type),
+    )
+    .await?;
+    if files.success() {
+        let states: HashMap<String, String> = files
+            .stdout_text()
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.split_whitespace();
+                Some((parts.next()?.to_string(), parts.next()?.to_string()))
+            })
+            .collect();
+        for unit in units.iter_mut() {
+            unit.unit_file_state = states.get(&unit.unit).cloned();
+        }
+    }
+    Ok(units)
+}
+
+/// 解析 `systemctl list-units --no-legend --plain`: UNIT LOAD ACTIVE SUB DESCRIPTION
+fn parse_list_units(text: &str) -> Vec<SystemdUnit> {
+    text.lines()
+        .filter_map(|line| {
+            // 部分版本即使 --plain 也会在失败单元前加 ●
+            let mut rest = line.trim_start().trim_start_matches('●');
+            let mut fields = Vec::with_capacity(4);
+            for _ in 0..4 {
+                rest = rest.trim_start();
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                if end == 0 {
+                    return None;
+                }
+                fields.push(rest[..end].to_string());
+                rest = &rest[end..];
+            }
+            let [unit, load, active, sub]: [String; 4] = fields.try_into().ok()?;
+            let description = rest.trim().to_string();
+            Some(SystemdUnit { unit, load, active, sub, description, unit_file_state: None })
+        })
+        .collect()
+}
+
+/// 单元状态及最近 lines 行日志（默认 50 行）
+#[tauri::command]
+pub async fn ssh_systemd_status(
+    session_id: String,
+    unit: String,
+    lines: Option<u32>,
+) -> Result<SystemdUnitStatus, String> {
+    check_unit(&unit)?;
+    let show = exec_output(
+        &session_id,
+        &format!("systemctl show --no-pager -p {} -- {}", SHOW_PROPERTIES, shell_quote(&unit)),
+    )
+    .await?;
+    if !show.success() {
+        return Err(show.error_message());
+    }
+    let properties = show
+        .stdout_text()
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            Some((key.to_string(), value.to_string()))
+        })
+        .collect();
+
+    let journal = exec_output(
+        &session_id,
+        &format!(
+            "journalctl -u {} -n {} --no-pager -o short-iso",
+            shell_quote(&unit),
+            lines.unwrap_or(50)
+        ),
+    )
+    .await?;
+    let (journal, journal_error) = if journal.success() {
+        let text = journal.stdout_text();
+        // 没有日志时 journalctl 输出 "-- No entries --"
+        let lines = text
+            .lines()
+            .filter(|line| !line.starts_with("-- "))
+            .map(|line| line.to_string())
+            .collect();
+        (lines, None)
+    } else {
+        (Vec::new(), Some(journal.error_message()))
+    };
+
+    Ok(SystemdUnitStatus { unit, properties, journal, journal_error })
+}
+
+/// 对单元执行 start/stop/restart/reload/enable/disable
+/// 非 root 用户通过 sudo 执行；需要密码时发送 ssh_sudo_prompt 事件等待前端回复
+#[tauri::command]
+pub async fn ssh_systemd_action(
+    app: AppHandle,
+    session_id: String,
+    unit: String,
+    action: String,
+) -> Result<(), String> {
+    check_unit(&unit)?;
+    if !["start", "stop", "restart", "reload", "enable", "disable"].contains(&action.as_str()) {
+        return Err(format!("Unsupported unit action: {}", action));
+    }
+    let command = format!("systemctl {} -- {}", action, shell_quote(&unit));
+    let output = exec_privileged(&app, &session_id, &command).await?;
+    if output.success() {
+        Ok(())
+    } else {
+        Err(output.error_message())
+    }
+}
+
+/// 回复 sudo 密码请求；password 为空表示取消
+#[tauri::command]
+pub async fn ssh_sudo_respond(request_id: String, password: Option<String>) -> Result<(), String> {
+    let tx = SUDO_PROMPTS.lock().unwrap().remove(&request_id);
+    match tx {
+        Some(tx) => tx
+            .send(password)
+            .map_err(|_| "Failed to send response, channel closed".to_string()),
+        None => Err(format!("No pending sudo prompt: {}", request_id)),
+    }
+}
+
+/// 以 root 身份执行命令：root 用户直接执行，否则先尝试免密 sudo，
+/// 需要密码时向前端请求，密码错误时重新请求，最多 3 次
+async fn exec_privileged(app: &AppHandle, session_id: &str, command: &str) -> Result<ExecOutput, String> {
+    let output = exec_output(
+        session_id,
+        &format!(
+            "if [ \"$(id -u)\" -eq 0 ]; then {cmd}; else LC_ALL=C sudo -n {cmd}; fi",
+            cmd = command
+        ),
+    )
+    .await?;
+    if output.success() || !String::from_utf8_lossy(&output.stderr).contains("password is required") {
+        return Ok(output);
+    }
+
+    for attempt in 0..3 {
+        let Some(password) = prompt_sudo_password(app, session_id, command, attempt > 0).await? else {
+            return Err("Sudo cancelled".to_string());
+        };
+        // -p '' 不输出提示；密码通过标准输入传入，不出现在命令行中
+        let channel = exec_channel(session_id, &format!("LC_ALL=C sudo -S -p '' {}", command)).await?;
+        channel
+            .data(format!("{}\n", password).as_bytes())
+            .await
+            .map_err(|e| e.to_string())?;
+        channel.eof().await.map_err(|e| e.to_string())?;
+        let output = wait_output(channel).await;
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if output.success() || !(stderr.contains("incorrect password") || stderr.contains("Sorry, try again")) {
+            return Ok(output);
+        }
+        info!("Incorrect sudo password for session {}", session_id);
+    }
+    Err("Incorrect sudo password".to_string())
+}
+
+/// 发送 ssh_sudo_prompt 事件并等待 ssh_sudo_respond，返回 None 表示用户取消
+async fn prompt_sudo_password(
+    app: &AppHandle,
+    session_id: &str,
+    command: &str,
+    retry: bool,
+) -> Result<Option<String>, String> {
+    let request_id = format!("sudo-{}", SUDO_PROMPT_ID.fetch_add(1, Ordering::Relaxed));
+    let (tx, rx) = tokio::sync::oneshot::channel::<Option<String>>();
+    SUDO_PROMPTS.lock().unwrap().insert(request_id.clone(), tx);
+
+    let _ = app.emit("ssh_sudo_prompt", SudoPromptPayload {
+        request_id: request_id.clone(),
+        session_id: session_id.to_string(),
+        command: command.to_string(),
+        retry,
+    });
+
+    let result = match tokio::time::timeout(SUDO_PROMPT_TIMEOUT, rx).await {
+        Ok(Ok(password)) => Ok(password),
+        // 通道被关闭，视为取消
+        Ok(Err(_)) => Ok(None),
+        Err(_) => Err("Sudo password prompt timed out".to_string()),
+    };
+
+    // 清理：无论成功、取消还是超时，都移除通道
+    SUDO_PROMPTS.lock().unwrap().remove(&request_id);
+    result
+}
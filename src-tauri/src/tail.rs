@@ -0,0 +1,442 @@
+use crate::exec::{exec_output, shell_quote};
+use crate::ssh::ssh_get_sftp;
+use crate::text::{self, TextEncoding};
+use once_cell::sync::Lazy;
+use russh_sftp::client::fs::File;
+use russh_sftp::client::SftpSession;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::SeekFrom;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::ipc::Channel;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+const TAIL_BUFFER_SIZE: usize = 64 * 1024;
+/// 读取初始行时最多回溯的字节数
+const INITIAL_MAX_BYTES: u64 = 1024 * 1024;
+/// 没有换行的超长行达到该长度时直接输出
+const MAX_LINE_BYTES: usize = 64 * 1024;
+/// 单个事件最多携带的行数
+const MAX_LINES_PER_EVENT: usize = 1000;
+/// 通过远端 stat 命令比较 inode 的间隔
+const INODE_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TailOptions {
+    /// 开始时输出末尾的行数
+    #[serde(default = "default_initial_lines")]
+    pub initial_lines: u32,
+    /// 轮询间隔（毫秒），最小 200
+    #[serde(default = "default_interval_ms")]
+    pub interval_ms: u64,
+    /// 只输出匹配该正则的行
+    #[serde(default)]
+    pub filter: Option<String>,
+    /// 需要高亮的正则，匹配位置随行返回
+    #[serde(default)]
+    pub highlight: Vec<String>,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    /// 文件编码，为空时按 UTF-8 解码；按字节 `\n` 分行，不支持 UTF-16
+    #[serde(default)]
+    pub encoding: Option<String>,
+}
+
+fn default_initial_lines() -> u32 { 100 }
+fn default_interval_ms() -> u64 { 1000 }
+
+/// 行内匹配位置，start/end 为 UTF-16 偏移，可直接用于 JS 字符串
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TailHighlight {
+    pub start: u32,
+    pub end: u32,
+    /// 对应 highlight 中的下标，filter 的匹配为 -1
+    pub pattern: i32,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TailLine {
+    pub text: String,
+    /// 该行在文件中的起始偏移
+    pub offset: u64,
+    pub highlights: Vec<TailHighlight>,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase",
+    tag = "event",
+    content = "data"
+)]
+pub enum TailEvent {
+    Lines {
+        lines: Vec<TailLine>,
+    },
+    /// 文件被截断（如 copytruncate），从头开始读取
+    Truncated,
+    /// 文件被轮转（原文件改名、新建同名文件），已切换到新文件
+    Rotated,
+    Error {
+        message: String,
+    },
+    Stopped,
+}
+
+struct TailTask {
+    session_id: String,
+    token: CancellationToken,
+}
+
+/// 跟踪任务（key: tail_id）
+static TAIL_TASKS: Lazy<Mutex<HashMap<String, TailTask>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 按偏移持续读取远端文件新增内容（类似 tail -F），不依赖交互式 shell
+#[tauri::command]
+pub async fn ssh_sftp_tail(
+    tail_id: String,
+    session_id: String,
+    file_path: String,
+    options: TailOptions,
+    on_tail_event: Channel<TailEvent>,
+) -> Result<(), String> {
+    let matcher = LineMatcher::new(&options)?;
+    let encoding = match &options.encoding {
+        Some(label) => Some(text::encoding_for_label(label, false)?),
+        None => None,
+    };
+    if encoding.as_ref().is_some_and(|e| e.is_utf16()) {
+        return Err("UTF-16 encoding is not supported for tail".to_string());
+    }
+    let sftp = ssh_get_sftp(&session_id).await?;
+    let file = sftp.open(file_path.as_str()).await.map_err(|e| e.to_string())?;
+
+    let token = CancellationToken::new();
+    {
+        let mut map = TAIL_TASKS.lock().await;
+        if map.contains_key(&tail_id) {
+            return Err("Tail already exists".to_string());
+        }
+        map.insert(
+            tail_id.clone(),
+            TailTask { session_id: session_id.clone(), token: token.clone() },
+        );
+    }
+
+    let mut tailer = Tailer {
+        session_id: session_id.clone(),
+        sftp,
+        file_path,
+        inode: None,
+        inode_checked: Instant::now(),
+        file,
+        offset: 0,
+        pending: Vec::new(),
+        pending_offset: 0,
+        matcher,
+        encoding,
+        on_tail_event: on_tail_event.clone(),
+    };
+    let interval = Duration::from_millis(options.interval_ms.max(200));
+    tokio::spawn(async move {
+        if let Err(message) = tailer.run(options.initial_lines, interval, &token).await {
+            let _ = on_tail_event.send(TailEvent::Error { message });
+        }
+        {
+            // 已取消说明条目已被 stop 移除，同一 tail_id 可能已被新任务使用，不能再移除
+            let mut map = TAIL_TASKS.lock().await;
+            if !token.is_cancelled() {
+                map.remove(&tail_id);
+            }
+        }
+        let _ = on_tail_event.send(TailEvent::Stopped);
+    });
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn ssh_sftp_tail_stop(tail_id: String) -> Result<(), String> {
+    let mut map = TAIL_TASKS.lock().await;
+
+    if let Some(task) = map.remove(&tail_id) {
+        task.token.cancel();
+        Ok(())
+    } else {
+        Err("任务不存在".to_string())
+    }
+}
+
+/// 会话关闭时结束该会话的所有跟踪
+pub async fn close_session_tails(session_id: &str) {
+    let mut map = TAIL_TASKS.lock().await;
+    map.retain(|_, task| {
+        if task.session_id == session_id {
+            task.token.cancel();
+            false
+        } else {
+            true
+        }
+    });
+}
+
+struct LineMatcher {
+    filter: Option<regex::Regex>,
+    highlight: Vec<regex::Regex>,
+}
+
+impl LineMatcher {
+    fn new(options: &TailOptions) -> Result<Self, String> {
+        let build = |pattern: &str| {
+            regex::RegexBuilder::new(pattern)
+                .case_insensitive(!options.case_sensitive)
+                .build()
+                .map_err(|e| format!("{}: {}", pattern, e))
+        };
+        Ok(Self {
+            filter: match options.filter.as_deref().filter(|p| !p.is_empty()) {
+                Some(pattern) => Some(build(pattern)?),
+                None => None,
+            },
+            highlight: options
+                .highlight
+                .iter()
+                .filter(|p| !p.is_empty())
+                .map(|p| build(p))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// 不满足 filter 时返回 None
+    fn apply(&self, text: String, offset: u64) -> Option<TailLine> {
+        let mut ranges: Vec<(usize, usize, i32)> = Vec::new();
+        if let Some(filter) = &self.filter {
+            ranges.extend(filter.find_iter(&text).map(|m| (m.start(), m.end(), -1)));
+            if ranges.is_empty() {
+                return None;
+            }
+        }
+        for (index, regex) in self.highlight.iter().enumerate() {
+            ranges.extend(regex.find_iter(&text).map(|m| (m.start(), m.end(), index as i32)));
+        }
+        let highlights = ranges
+            .into_iter()
+            .filter(|(start, end, _)| start < end)
+            .map(|(start, end, pattern)| TailHighlight {
+                start: utf16_len(&text[..start]),
+                end: utf16_len(&text[..end]),
+                pattern,
+            })
+            .collect();
+        Some(TailLine { text, offset, highlights })
+    }
+}
+
+fn utf16_len(s: &str) -> u32 {
+    s.encode_utf16().count() as u32
+}
+
+struct Tailer {
+    session_id: String,
+    sftp: Arc<SftpSession>,
+    file_path: String,
+    /// 当前跟踪文件的 inode（远端无 stat 命令时为 None）
+    inode: Option<u64>,
+    /// 上次通过 stat 命令检查 inode 的时间
+    inode_checked: Instant,
+    file: File,
+    /// 已读取到的文件偏移
+    offset: u64,
+    /// 尚未遇到换行的不完整行
+    pending: Vec<u8>,
+    pending_offset: u64,
+    matcher: LineMatcher,
+    encoding: Option<TextEncoding>,
+    on_tail_event: Channel<TailEvent>,
+}
+
+impl Tailer {
+    async fn run(&mut self, initial_lines: u32, interval: Duration, token: &CancellationToken) -> Result<(), String> {
+        self.inode = self.path_inode().await;
+        let size = self.file_size().await?;
+        self.offset = self.initial_offset(size, initial_lines).await?;
+        self.pending_offset = self.offset;
+        self.read_new().await?;
+
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => return Ok(()),
+                _ = tokio::time::sleep(interval) => {}
+            }
+
+            // 先读完当前句柄的新增内容，轮转时旧文件的末尾不会丢失
+            self.read_new().await?;
+            let opened_size = self.file_size().await?;
+            if opened_size < self.offset {
+                self.offset = 0;
+                self.pending.clear();
+                self.pending_offset = 0;
+                let _ = self.on_tail_event.send(TailEvent::Truncated);
+                self.read_new().await?;
+            }
+            if self.is_rotated(opened_size).await && self.reopen().await {
+                let _ = self.on_tail_event.send(TailEvent::Rotated);
+                self.read_new().await?;
+            }
+        }
+    }
+
+    /// 远端 `stat` 输出的 inode，命令不可用时返回 None
+    async fn path_inode(&self) -> Option<u64> {
+        let output = exec_output(
+            &self.session_id,
+            &format!("stat -L -c %i -- {}", shell_quote(&self.file_path)),
+        )
+        .await
+        .ok()?;
+        if !output.success() {
+            return None;
+        }
+        output.stdout_text().trim().parse().ok()
+    }
+
+    /// 判断路径是否已指向另一个文件
+    /// 路径上的文件比已打开句柄的文件小即视为轮转（opened_size 先于路径 stat 获取，文件增长不会误判），
+    /// sftp stat 开销小，每次轮询都检查；新文件已长得比旧文件大的情况只能比较 inode，
+    /// stat 命令需要新开 exec 通道，每 INODE_CHECK_INTERVAL 检查一次
+    async fn is_rotated(&mut self, opened_size: u64) -> bool {
+        let smaller = match self.sftp.metadata(self.file_path.as_str()).await {
+            Ok(meta) => meta.size.unwrap_or(0) < opened_size,
+            // 轮转过程中文件可能短暂不存在
+            Err(_) => false,
+        };
+        let Some(inode) = self.inode else {
+            return smaller;
+        };
+        if !smaller && self.inode_checked.elapsed() < INODE_CHECK_INTERVAL {
+            return false;
+        }
+        self.inode_checked = Instant::now();
+        match self.path_inode().await {
+            Some(current) if current != inode => {
+                self.inode = Some(current);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    async fn file_size(&mut self) -> Result<u64, String> {
+        Ok(self
+            .file
+            .metadata()
+            .await
+            .map_err(|e| e.to_string())?
+            .size
+            .unwrap_or(0))
+    }
+
+    /// 文件轮转后重新打开同名文件，从头读取
+    async fn reopen(&mut self) -> bool {
+        let Ok(file) = self.sftp.open(self.file_path.as_str()).await else {
+            return false;
+        };
+        self.flush_pending();
+        self.file = file;
+        self.offset = 0;
+        self.pending_offset = 0;
+        true
+    }
+
+    /// 从末尾向前查找，返回倒数第 lines 行的起始偏移
+    async fn initial_offset(&mut self, size: u64, lines: u32) -> Result<u64, String> {
+        if lines == 0 || size == 0 {
+            return Ok(size);
+        }
+        let mut end = size;
+        let mut newlines: u32 = 0;
+        let mut buffer = vec![0u8; TAIL_BUFFER_SIZE];
+        while end > 0 && size - end < INITIAL_MAX_BYTES {
+            let start = end.saturating_sub(TAIL_BUFFER_SIZE as u64);
+            let len = (end - start) as usize;
+            self.file.seek(SeekFrom::Start(start)).await.map_err(|e| e.to_string())?;
+            self.file
+                .read_exact(&mut buffer[..len])
+                .await
+                .map_err(|e| e.to_string())?;
+            for i in (0..len).rev() {
+                // 忽略文件末尾的换行
+                if buffer[i] == b'\n' && start + i as u64 != size - 1 {
+                    newlines += 1;
+                    if newlines == lines {
+                        return Ok(start + i as u64 + 1);
+                    }
+                }
+            }
+            end = start;
+        }
+        Ok(end)
+    }
+
+    /// 读取 offset 之后的新内容并按行发送
+    async fn read_new(&mut self) -> Result<(), String> {
+        self.file
+            .seek(SeekFrom::Start(self.offset))
+            .await
+            .map_err(|e| e.to_string())?;
+        let mut buffer = vec![0u8; TAIL_BUFFER_SIZE];
+        let mut lines: Vec<TailLine> = Vec::new();
+        loop {
+            let n = self.file.read(&mut buffer).await.map_err(|e| e.to_string())?;
+            if n == 0 {
+                break;
+            }
+            for &byte in &buffer[..n] {
+                self.offset += 1;
+                if byte != b'\n' {
+                    self.pending.push(byte);
+                    if self.pending.len() < MAX_LINE_BYTES {
+                        continue;
+                    }
+                }
+                if let Some(line) = self.take_line() {
+                    lines.push(line);
+                }
+                self.pending_offset = self.offset;
+            }
+            if lines.len() >= MAX_LINES_PER_EVENT {
+                let _ = self.on_tail_event.send(TailEvent::Lines { lines: std::mem::take(&mut lines) });
+            }
+        }
+        if !lines.is_empty() {
+            let _ = self.on_tail_event.send(TailEvent::Lines { lines });
+        }
+        Ok(())
+    }
+
+    fn take_line(&mut self) -> Option<TailLine> {
+        let bytes = std::mem::take(&mut self.pending);
+        let text = match &self.encoding {
+            Some(encoding) => text::decode(&bytes, encoding),
+            None => String::from_utf8_lossy(&bytes).to_string(),
+        };
+        let text = text.trim_end_matches('\r').to_string();
+        self.matcher.apply(text, self.pending_offset)
+    }
+
+    /// 切换文件前输出旧文件末尾没有换行的内容
+    fn flush_pending(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+        if let Some(line) = self.take_line() {
+            let _ = self.on_tail_event.send(TailEvent::Lines { lines: vec![line] });
+        }
+    }
+}
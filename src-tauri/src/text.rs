@@ -0,0 +1,212 @@
+use encoding_rs::{Encoding, GB18030, SHIFT_JIS, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+
+/// 用于检测编码的采样大小
+pub const DETECT_SAMPLE_SIZE: usize = 64 * 1024;
+
+/// 检测到的文本编码
+#[derive(Clone, Copy)]
+pub struct TextEncoding {
+    pub encoding: &'static Encoding,
+    /// 文件是否以 BOM 开头
+    pub bom: bool,
+}
+
+impl TextEncoding {
+    pub fn name(&self) -> String {
+        self.encoding.name().to_string()
+    }
+
+    pub fn is_utf16(&self) -> bool {
+        self.encoding == UTF_16LE || self.encoding == UTF_16BE
+    }
+}
+
+/// 文件开头 BOM 的字节长度，按实际字节判断，与选用的编码无关
+pub fn bom_len(data: &[u8]) -> usize {
+    if data.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        3
+    } else if data.starts_with(&[0xFF, 0xFE]) || data.starts_with(&[0xFE, 0xFF]) {
+        2
+    } else {
+        0
+    }
+}
+
+/// 按名称（如 "UTF-8"、"GBK"、"Shift_JIS"、"ISO-8859-1"）获取编码
+pub fn encoding_for_label(label: &str, bom: bool) -> Result<TextEncoding, String> {
+    let encoding = Encoding::for_label(label.trim().as_bytes())
+        .ok_or_else(|| format!("Unsupported encoding: {}", label))?;
+    Ok(TextEncoding { encoding, bom })
+}
+
+/// 根据文件开头的采样检测编码：BOM → UTF-8 → GB18030/Shift_JIS → Latin-1
+/// `complete` 表示采样是否已包含整个文件（否则末尾可能截断了多字节字符）
+pub fn detect_encoding(sample: &[u8], complete: bool) -> Result<TextEncoding, String> {
+    if sample.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        return Ok(TextEncoding { encoding: UTF_8, bom: true });
+    }
+    if sample.starts_with(&[0xFF, 0xFE]) {
+        return Ok(TextEncoding { encoding: UTF_16LE, bom: true });
+    }
+    if sample.starts_with(&[0xFE, 0xFF]) {
+        return Ok(TextEncoding { encoding: UTF_16BE, bom: true });
+    }
+    if sample.contains(&0) {
+        return Err("Binary file is not supported".to_string());
+    }
+
+    // 采样被截断时只检测到最后一个换行为止
+    let sample = if complete {
+        sample
+    } else {
+        match sample.iter().rposition(|b| *b == b'\n') {
+            Some(pos) => &sample[..=pos],
+            None => sample,
+        }
+    };
+    match std::str::from_utf8(sample) {
+        Ok(_) => return Ok(TextEncoding { encoding: UTF_8, bom: false }),
+        // 仅末尾字符不完整
+        Err(e) if !complete && e.error_len().is_none() => {
+            return Ok(TextEncoding { encoding: UTF_8, bom: false })
+        }
+        Err(_) => {}
+    }
+
+    let gb = GB18030.decode_without_bom_handling_and_without_replacement(sample);
+    let sjis = SHIFT_JIS.decode_without_bom_handling_and_without_replacement(sample);
+    let encoding = match (gb, sjis) {
+        // 两者都能解码时，假名占比高的判定为日文
+        (Some(_), Some(sjis)) if kana_ratio(&sjis) >= 0.25 => SHIFT_JIS,
+        (Some(_), _) => GB18030,
+        (None, Some(_)) => SHIFT_JIS,
+        (None, None) => WINDOWS_1252,
+    };
+    Ok(TextEncoding { encoding, bom: false })
+}
+
+/// 非 ASCII 字符中平假名/片假名的占比
+fn kana_ratio(text: &str) -> f64 {
+    let mut non_ascii = 0;
+    let mut kana = 0;
+    for c in text.chars().filter(|c| !c.is_ascii()) {
+        non_ascii += 1;
+        if ('\u{3040}'..='\u{30FF}').contains(&c) {
+            kana += 1;
+        }
+    }
+    if non_ascii == 0 {
+        0.0
+    } else {
+        kana as f64 / non_ascii as f64
+    }
+}
+
+/// 按指定编码解码（不含 BOM 的字节）
+pub fn decode(bytes: &[u8], encoding: &TextEncoding) -> String {
+    let (text, _) = encoding.encoding.decode_without_bom_handling(bytes);
+    text.into_owned()
+}
+
+/// 按指定编码和换行符编码文本（content 中的换行统一视为 \n），写回时使用
+pub fn encode(content: &str, encoding: &TextEncoding, line_ending: &str) -> Result<Vec<u8>, String> {
+    let normalized = normalize_line_endings(content);
+    let text = match line_ending {
+        "CRLF" => normalized.replace('\n', "\r\n"),
+        "CR" => normalized.replace('\n', "\r"),
+        _ => normalized,
+    };
+
+    let mut out = Vec::with_capacity(text.len() + 3);
+    if encoding.encoding == UTF_16LE || encoding.encoding == UTF_16BE {
+        // encoding_rs 不提供 UTF-16 编码器，手动转换
+        if encoding.bom {
+            out.extend_from_slice(if encoding.encoding == UTF_16LE { &[0xFF, 0xFE] } else { &[0xFE, 0xFF] });
+        }
+        for unit in text.encode_utf16() {
+            if encoding.encoding == UTF_16LE {
+                out.extend_from_slice(&unit.to_le_bytes());
+            } else {
+                out.extend_from_slice(&unit.to_be_bytes());
+            }
+        }
+        return Ok(out);
+    }
+
+    if encoding.bom && encoding.encoding == UTF_8 {
+        out.extend_from_slice(&[0xEF, 0xBB, 0xBF]);
+    }
+    let (bytes, _, unmappable) = encoding.encoding.encode(&text);
+    if unmappable {
+        return Err(format!(
+            "Content contains characters that cannot be encoded in {}",
+            encoding.encoding.name()
+        ));
+    }
+    out.extend_from_slice(&bytes);
+    Ok(out)
+}
+
+/// 统一换行为 \n
+pub fn normalize_line_endings(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+/// 检测换行符类型（以出现次数最多的为准）: "LF"、"CRLF" 或 "CR"
+pub fn detect_line_ending(text: &str) -> &'static str {
+    let bytes = text.as_bytes();
+    let (mut lf, mut crlf, mut cr) = (0, 0, 0);
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\r' if bytes.get(i + 1) == Some(&b'\n') => {
+                crlf += 1;
+                i += 1;
+            }
+            b'\r' => cr += 1,
+            b'\n' => lf += 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    if crlf > lf && crlf >= cr {
+        "CRLF"
+    } else if cr > lf && cr > crlf {
+        "CR"
+    } else {
+        "LF"
+    }
+}
+
+/// 分页读取时，返回 buf 中可安全解码的长度（截止到最后一个换行之后），避免切断多字节字符
+/// 单行超过一页时退回到最后一个完整字符之后
+pub fn page_boundary(buf: &[u8], encoding: &TextEncoding) -> usize {
+    let newline = if encoding.is_utf16() {
+        let newline: [u8; 2] = if encoding.encoding == UTF_16LE { [b'\n', 0] } else { [0, b'\n'] };
+        let even = buf.len() & !1;
+        (0..even)
+            .step_by(2)
+            .rev()
+            .find(|i| buf[*i..*i + 2] == newline)
+            .map(|i| i + 2)
+    } else {
+        // GB18030、Shift_JIS 等的后续字节都不会是 \n
+        buf.iter().rposition(|b| *b == b'\n').map(|pos| pos + 1)
+    };
+    newline.unwrap_or_else(|| char_boundary(buf, encoding))
+}
+
+/// 去掉末尾不完整的字符：最多回退 4 字节（UTF-8 最长字符、UTF-16 代理对加奇数字节），
+/// 找到能完整解码的最长前缀；中间本身含无效字节时不回退
+fn char_boundary(buf: &[u8], encoding: &TextEncoding) -> usize {
+    let min = buf.len().saturating_sub(4);
+    (min..=buf.len())
+        .rev()
+        .find(|end| {
+            encoding
+                .encoding
+                .decode_without_bom_handling_and_without_replacement(&buf[..*end])
+                .is_some()
+        })
+        .unwrap_or(buf.len())
+}
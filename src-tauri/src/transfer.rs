@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+use std::time::Instant;
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+/// 可取消的传输任务（key: task_id）
+pub type TransferTasks = Mutex<HashMap<String, CancellationToken>>;
+
+/// 传输任务结束时发送给前端的事件
+pub trait TransferEvent: serde::Serialize + Clone {
+    fn finished(transferred: u64, elapsed_ms: u64) -> Self;
+    fn cancelled() -> Self;
+}
+
+/// 登记任务并返回其取消令牌
+pub async fn register_task(tasks: &TransferTasks, task_id: &str) -> CancellationToken {
+    let token = CancellationToken::new();
+    tasks.lock().await.insert(task_id.into(), token.clone());
+    token
+}
+
+/// 根据传输结果发送 Finished；错误信息为 cancelled 时发送 Cancelled
+pub fn finish<E: TransferEvent>(
+    result: Result<u64, String>,
+    started: Instant,
+    cancelled: &str,
+    on_event: &tauri::ipc::Channel<E>,
+) -> Result<String, String> {
+    match result {
+        Ok(transferred) => {
+            let _ = on_event.send(E::finished(transferred, started.elapsed().as_millis() as u64));
+            Ok("ok".to_string())
+        }
+        Err(e) => {
+            if e == cancelled {
+                let _ = on_event.send(E::cancelled());
+            }
+            Err(e)
+        }
+    }
+}
+
+/// 进度百分比（0-100），总量为 0 时视为已完成
+pub fn percent(done: u64, total: u64) -> u32 {
+    if total == 0 {
+        return 100;
+    }
+    ((done as f64 / total as f64) * 100.0).min(100.0) as u32
+}
+
+/// 平均速率 (bytes/s)
+pub fn transfer_speed(transferred: u64, started: Instant) -> f64 {
+    let elapsed = started.elapsed().as_secs_f64();
+    if elapsed > 0.0 {
+        transferred as f64 / elapsed
+    } else {
+        0.0
+    }
+}
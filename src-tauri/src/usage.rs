@@ -0,0 +1,427 @@
+use crate::exec::{exec_channel, shell_quote};
+use crate::sftp::remote_join;
+use crate::ssh::ssh_get_sftp;
+use log::info;
+use once_cell::sync::Lazy;
+use russh_sftp::client::SftpSession;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+/// 磁盘占用分析任务（key: task_id）
+static USAGE_TASKS: Lazy<Mutex<HashMap<String, CancellationToken>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 部分结果的推送间隔
+const PARTIAL_INTERVAL: Duration = Duration::from_millis(250);
+/// 单批部分结果的最大条目数
+const PARTIAL_BATCH_SIZE: usize = 500;
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskUsageOptions {
+    /// 返回的层级深度，起始目录的直接子项为 1
+    #[serde(default = "default_max_depth")]
+    pub max_depth: u32,
+    /// 每层保留的最大条目数，其余条目合并统计
+    #[serde(default = "default_top_n")]
+    pub top_n: u32,
+    /// 不跨越文件系统（du -x），sftp 后端忽略此项
+    #[serde(default)]
+    pub one_file_system: bool,
+    /// "auto"（优先 du，不可用时回退 sftp）、"du" 或 "sftp"
+    #[serde(default = "default_backend")]
+    pub backend: String,
+}
+
+fn default_max_depth() -> u32 { 3 }
+fn default_top_n() -> u32 { 20 }
+fn default_backend() -> String { "auto".to_string() }
+
+/// 单个已统计完成的条目
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskUsageEntry {
+    pub path: String,
+    pub size: u64,
+}
+
+/// 占用树节点，children 按大小降序
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskUsageNode {
+    pub name: String,
+    pub path: String,
+    pub size: u64,
+    /// du 后端只能根据是否有子项判断，空目录及达到 max_depth 的目录会被视为文件
+    pub is_dir: bool,
+    pub children: Vec<DiskUsageNode>,
+    /// 超出 top_n 未列出的子项数量
+    pub other_count: u32,
+    /// 超出 top_n 未列出的子项总大小
+    pub other_size: u64,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase",
+    tag = "event",
+    content = "data"
+)]
+pub enum DiskUsageEvent {
+    /// 扫描中已统计完成的条目（子项总是先于其父目录完成），用于边扫描边渲染
+    Partial {
+        entries: Vec<DiskUsageEntry>,
+    },
+    /// du 中途失败、改用 sftp 重新扫描，此前推送的部分结果和目录错误作废
+    Reset,
+    /// 某个目录无法读取，继续扫描其余目录
+    DirError {
+        path: String,
+        message: String,
+    },
+    Finished {
+        tree: DiskUsageNode,
+        /// 实际使用的后端: "du" 或 "sftp"
+        backend: String,
+        elapsed_ms: u64,
+    },
+    Cancelled,
+}
+
+/// (路径, 大小, 是否目录)，du 后端无法得知是否目录
+type UsageItem = (String, u64, Option<bool>);
+
+enum UsageOutcome {
+    Done(Vec<UsageItem>),
+    /// du 不可用，需回退
+    Unsupported,
+    Cancelled,
+}
+
+/// 统计远端目录的磁盘占用，扫描过程中推送部分结果，完成后推送前 top_n 的占用树
+/// du 后端统计的是磁盘占用（按块），sftp 后端统计的是文件实际大小
+#[tauri::command]
+pub async fn ssh_disk_usage(
+    task_id: &str,
+    session_id: &str,
+    dir: &str,
+    options: DiskUsageOptions,
+    on_usage_event: tauri::ipc::Channel<DiskUsageEvent>,
+) -> Result<u64, String> {
+    let root = if dir.len() > 1 { dir.trim_end_matches('/') } else { dir };
+    let max_depth = options.max_depth.max(1);
+
+    let token = CancellationToken::new();
+    {
+        let mut map = USAGE_TASKS.lock().await;
+        map.insert(task_id.into(), token.clone());
+    }
+    let started = Instant::now();
+
+    let result = async {
+        let outcome = match options.backend.as_str() {
+            "sftp" => UsageOutcome::Unsupported,
+            _ => usage_du(session_id, root, max_depth, options.one_file_system, &token, &on_usage_event).await?,
+        };
+        if !matches!(outcome, UsageOutcome::Unsupported) {
+            return Ok((outcome, "du"));
+        }
+        if options.backend == "du" {
+            return Err("du is not available on the remote host".to_string());
+        }
+        info!("du unavailable, fall back to sftp walk: {}", root);
+        if options.backend != "sftp" {
+            let _ = on_usage_event.send(DiskUsageEvent::Reset);
+        }
+        let outcome = usage_sftp(session_id, root, max_depth, &token, &on_usage_event).await?;
+        Ok((outcome, "sftp"))
+    }
+    .await;
+
+    USAGE_TASKS.lock().await.remove(task_id);
+    let (outcome, backend) = result?;
+
+    match outcome {
+        UsageOutcome::Done(items) => {
+            let tree = build_tree(root, items, options.top_n.max(1) as usize);
+            let total = tree.size;
+            let _ = on_usage_event.send(DiskUsageEvent::Finished {
+                tree,
+                backend: backend.to_string(),
+                elapsed_ms: started.elapsed().as_millis() as u64,
+            });
+            Ok(total)
+        }
+        _ => {
+            let _ = on_usage_event.send(DiskUsageEvent::Cancelled);
+            Err("Disk usage scan cancelled".to_string())
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn ssh_disk_usage_cancel(task_id: String) -> Result<(), String> {
+    let mut map = USAGE_TASKS.lock().await;
+
+    if let Some(token) = map.remove(&task_id) {
+        token.cancel();
+        Ok(())
+    } else {
+        Err("任务不存在".to_string())
+    }
+}
+
+/// 节流推送部分结果
+struct PartialSender<'a> {
+    on_usage_event: &'a tauri::ipc::Channel<DiskUsageEvent>,
+    batch: Vec<DiskUsageEntry>,
+    last_flush: Instant,
+}
+
+impl<'a> PartialSender<'a> {
+    fn new(on_usage_event: &'a tauri::ipc::Channel<DiskUsageEvent>) -> Self {
+        Self { on_usage_event, batch: Vec::new(), last_flush: Instant::now() }
+    }
+
+    fn push(&mut self, path: &str, size: u64) {
+        self.batch.push(DiskUsageEntry { path: path.to_string(), size });
+        if self.batch.len() >= PARTIAL_BATCH_SIZE || self.last_flush.elapsed() >= PARTIAL_INTERVAL {
+            self.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        self.last_flush = Instant::now();
+        if !self.batch.is_empty() {
+            let entries = std::mem::take(&mut self.batch);
+            let _ = self.on_usage_event.send(DiskUsageEvent::Partial { entries });
+        }
+    }
+}
+
+/// `du -a -k -d N`：按后序输出 `大小(KB)\t路径`
+async fn usage_du(
+    session_id: &str,
+    root: &str,
+    max_depth: u32,
+    one_file_system: bool,
+    token: &CancellationToken,
+    on_usage_event: &tauri::ipc::Channel<DiskUsageEvent>,
+) -> Result<UsageOutcome, String> {
+    let command = format!(
+        "du -a -k{} -d {} -- {}",
+        if one_file_system { " -x" } else { "" },
+        max_depth,
+        shell_quote(root)
+    );
+    let mut channel = exec_channel(session_id, &command).await?;
+    let mut stdout: Vec<u8> = Vec::new();
+    let mut stderr: Vec<u8> = Vec::new();
+    let mut items: Vec<UsageItem> = Vec::new();
+    let mut partial = PartialSender::new(on_usage_event);
+    let mut exit_status: Option<u32> = None;
+    // 根路径本身无法访问（如不存在）时的错误信息
+    let mut root_error: Option<String> = None;
+
+    loop {
+        let msg = tokio::select! {
+            _ = token.cancelled() => {
+                let _ = channel.close().await;
+                return Ok(UsageOutcome::Cancelled);
+            }
+            msg = channel.wait() => msg,
+        };
+        match msg {
+            Some(russh::ChannelMsg::Data { ref data }) => {
+                stdout.extend_from_slice(data);
+                while let Some(pos) = stdout.iter().position(|b| *b == b'\n') {
+                    let line: Vec<u8> = stdout.drain(..=pos).collect();
+                    let Some((size, path)) = parse_du_line(&line) else {
+                        continue;
+                    };
+                    partial.push(&path, size);
+                    items.push((path, size, None));
+                }
+            }
+            Some(russh::ChannelMsg::ExtendedData { ref data, ext: 1 }) => {
+                stderr.extend_from_slice(data);
+                while let Some(pos) = stderr.iter().position(|b| *b == b'\n') {
+                    let line: Vec<u8> = stderr.drain(..=pos).collect();
+                    let message = String::from_utf8_lossy(&line).trim().to_string();
+                    let path = quoted_path(&message).unwrap_or_default();
+                    if path == root && root_error.is_none() {
+                        root_error = Some(message.clone());
+                    }
+                    let _ = on_usage_event.send(DiskUsageEvent::DirError { path, message });
+                }
+            }
+            Some(russh::ChannelMsg::ExitStatus { exit_status: status }) => {
+                exit_status = Some(status);
+            }
+            Some(russh::ChannelMsg::Close) | None => break,
+            _ => {}
+        }
+    }
+    partial.flush();
+
+    // 1 可能只是部分目录无权限；根目录的统计行总是最后输出
+    // 根路径本身出错时直接报错，不回退到 sftp（回退只会得到大小 0）
+    match exit_status {
+        Some(0) | Some(1) if items.last().is_some_and(|item| item.0 == root) => Ok(UsageOutcome::Done(items)),
+        Some(1) if root_error.is_some() => Err(root_error.unwrap_or_default()),
+        _ => Ok(UsageOutcome::Unsupported),
+    }
+}
+
+/// 解析 `size\tpath\n`
+fn parse_du_line(line: &[u8]) -> Option<(u64, String)> {
+    let line = String::from_utf8_lossy(line);
+    let (size, path) = line.trim_end_matches(['\r', '\n']).split_once('\t')?;
+    let size: u64 = size.trim().parse().ok()?;
+    Some((size * 1024, path.to_string()))
+}
+
+/// 从 `du: cannot read directory '/root': Permission denied` 中提取路径
+fn quoted_path(message: &str) -> Option<String> {
+    let start = message.find(['\'', '‘'])?;
+    let rest = &message[start..];
+    let quote_len = rest.chars().next()?.len_utf8();
+    let end = rest[quote_len..].find(['\'', '’'])?;
+    Some(rest[quote_len..quote_len + end].to_string())
+}
+
+/// 纯 sftp 回退：深度优先遍历，统计文件实际大小
+async fn usage_sftp(
+    session_id: &str,
+    root: &str,
+    max_depth: u32,
+    token: &CancellationToken,
+    on_usage_event: &tauri::ipc::Channel<DiskUsageEvent>,
+) -> Result<UsageOutcome, String> {
+    let sftp = ssh_get_sftp(session_id).await?;
+    let metadata = sftp.metadata(root).await.map_err(|e| e.to_string())?;
+    if !metadata.file_type().is_dir() {
+        let size = metadata.size.unwrap_or(0);
+        return Ok(UsageOutcome::Done(vec![(root.to_string(), size, Some(false))]));
+    }
+    let mut walker = SftpWalker {
+        sftp: &sftp,
+        token,
+        max_depth,
+        on_usage_event,
+        partial: PartialSender::new(on_usage_event),
+        items: Vec::new(),
+    };
+    let Some(total) = walker.walk(root.to_string(), 0).await else {
+        return Ok(UsageOutcome::Cancelled);
+    };
+    walker.partial.push(root, total);
+    walker.partial.flush();
+    walker.items.push((root.to_string(), total, Some(true)));
+    Ok(UsageOutcome::Done(walker.items))
+}
+
+struct SftpWalker<'a> {
+    sftp: &'a SftpSession,
+    token: &'a CancellationToken,
+    max_depth: u32,
+    on_usage_event: &'a tauri::ipc::Channel<DiskUsageEvent>,
+    partial: PartialSender<'a>,
+    items: Vec<UsageItem>,
+}
+
+impl<'a> SftpWalker<'a> {
+    /// 返回目录总大小，取消时返回 None；超过 max_depth 的子项只计入大小不单独记录
+    fn walk<'b>(&'b mut self, dir: String, depth: u32) -> Pin<Box<dyn Future<Output = Option<u64>> + Send + 'b>> {
+        Box::pin(async move {
+            let entries = tokio::select! {
+                _ = self.token.cancelled() => return None,
+                res = self.sftp.read_dir(dir.as_str()) => res,
+            };
+            let entries = match entries {
+                Ok(entries) => entries,
+                Err(e) => {
+                    let _ = self.on_usage_event.send(DiskUsageEvent::DirError {
+                        path: dir,
+                        message: e.to_string(),
+                    });
+                    return Some(0);
+                }
+            };
+
+            let mut total: u64 = 0;
+            for entry in entries {
+                let metadata = entry.metadata();
+                let file_type = metadata.file_type();
+                // 符号链接不跟随，避免重复统计和循环
+                if file_type.is_symlink() {
+                    continue;
+                }
+                let path = remote_join(&dir, &entry.file_name());
+                let size = if file_type.is_dir() {
+                    self.walk(path.clone(), depth + 1).await?
+                } else {
+                    metadata.size.unwrap_or(0)
+                };
+                total += size;
+                if depth < self.max_depth {
+                    self.partial.push(&path, size);
+                    self.items.push((path, size, Some(file_type.is_dir())));
+                }
+            }
+            Some(total)
+        })
+    }
+}
+
+fn parent_of(path: &str) -> &str {
+    match path.rfind('/') {
+        Some(0) => "/",
+        Some(pos) => &path[..pos],
+        None => "",
+    }
+}
+
+/// 由扁平条目构建占用树，每层只保留最大的 top_n 项
+fn build_tree(root: &str, items: Vec<UsageItem>, top_n: usize) -> DiskUsageNode {
+    let mut root_size = 0;
+    let mut children: HashMap<String, Vec<UsageItem>> = HashMap::new();
+    for item in items {
+        if item.0 == root {
+            root_size = item.1;
+        } else {
+            children.entry(parent_of(&item.0).to_string()).or_default().push(item);
+        }
+    }
+    build_node((root.to_string(), root_size, Some(true)), &mut children, top_n)
+}
+
+fn build_node(
+    (path, size, is_dir): UsageItem,
+    children_map: &mut HashMap<String, Vec<UsageItem>>,
+    top_n: usize,
+) -> DiskUsageNode {
+    let mut kids = children_map.remove(&path).unwrap_or_default();
+    kids.sort_by_key(|item| std::cmp::Reverse(item.1));
+    let others = if kids.len() > top_n { kids.split_off(top_n) } else { Vec::new() };
+    let children: Vec<DiskUsageNode> = kids
+        .into_iter()
+        .map(|item| build_node(item, children_map, top_n))
+        .collect();
+
+    DiskUsageNode {
+        name: path.rsplit('/').next().filter(|s| !s.is_empty()).unwrap_or(&path).to_string(),
+        is_dir: is_dir.unwrap_or(!children.is_empty()),
+        path,
+        size,
+        children,
+        other_count: others.len() as u32,
+        other_size: others.iter().map(|item| item.1).sum(),
+    }
+}
@@ -0,0 +1,342 @@
+use crate::exec::{exec_channel, exec_output, shell_quote};
+use crate::sftp::remote_join;
+use crate::ssh::ssh_get_sftp;
+use log::info;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::ipc::Channel;
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+/// inotifywait 事件合并的间隔（同一文件持续写入时只上报一次）
+const INOTIFY_COALESCE: Duration = Duration::from_millis(300);
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirWatchOptions {
+    /// 轮询间隔（毫秒），最小 500
+    #[serde(default = "default_interval_ms")]
+    pub interval_ms: u64,
+    /// "auto"（优先 inotifywait，不可用时轮询）、"inotify" 或 "poll"
+    #[serde(default = "default_backend")]
+    pub backend: String,
+}
+
+fn default_interval_ms() -> u64 { 2000 }
+fn default_backend() -> String { "auto".to_string() }
+
+#[derive(Clone, serde::Serialize)]
+#[serde(
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase",
+    tag = "event",
+    content = "data"
+)]
+pub enum DirWatchEvent {
+    Added {
+        path: String,
+        filename: String,
+        is_dir: bool,
+        size: u64,
+        mtime: u64,
+    },
+    Removed {
+        path: String,
+        filename: String,
+    },
+    Modified {
+        path: String,
+        filename: String,
+        is_dir: bool,
+        size: u64,
+        mtime: u64,
+    },
+    /// 监听出错（如目录被删除），监听随之结束
+    Error {
+        message: String,
+    },
+    Stopped,
+}
+
+struct WatchTask {
+    session_id: String,
+    token: CancellationToken,
+}
+
+/// 目录监听任务（key: watch_id）
+static WATCH_TASKS: Lazy<Mutex<HashMap<String, WatchTask>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 监听远端目录（不递归）的增删改，返回实际使用的后端: "inotify" 或 "poll"
+#[tauri::command]
+pub async fn ssh_sftp_watch(
+    watch_id: String,
+    session_id: String,
+    dir: String,
+    options: DirWatchOptions,
+    on_watch_event: Channel<DirWatchEvent>,
+) -> Result<String, String> {
+    let use_inotify = match options.backend.as_str() {
+        "poll" => false,
+        backend => {
+            let available = exec_output(&session_id, "command -v inotifywait")
+                .await
+                .map(|output| output.success())
+                .unwrap_or(false);
+            if !available && backend == "inotify" {
+                return Err("inotifywait is not available on the remote host".to_string());
+            }
+            available
+        }
+    };
+
+    let token = CancellationToken::new();
+    {
+        let mut map = WATCH_TASKS.lock().await;
+        if map.contains_key(&watch_id) {
+            return Err("Watch already exists".to_string());
+        }
+        map.insert(
+            watch_id.clone(),
+            WatchTask { session_id: session_id.clone(), token: token.clone() },
+        );
+    }
+
+    let interval = Duration::from_millis(options.interval_ms.max(500));
+    tokio::spawn(async move {
+        let mut result = Ok(());
+        if use_inotify {
+            result = watch_inotify(&session_id, &dir, &token, &on_watch_event).await;
+            if let Err(e) = &result {
+                info!("inotifywait stopped, fall back to polling: {}", e);
+            }
+        }
+        if !token.is_cancelled() && (!use_inotify || result.is_err()) {
+            result = watch_poll(&session_id, &dir, interval, &token, &on_watch_event).await;
+        }
+        if let Err(message) = result {
+            let _ = on_watch_event.send(DirWatchEvent::Error { message });
+        }
+        {
+            // 已取消说明条目已被 stop 移除，同一 watch_id 可能已被新任务使用，不能再移除
+            let mut map = WATCH_TASKS.lock().await;
+            if !token.is_cancelled() {
+                map.remove(&watch_id);
+            }
+        }
+        let _ = on_watch_event.send(DirWatchEvent::Stopped);
+    });
+
+    Ok(if use_inotify { "inotify" } else { "poll" }.to_string())
+}
+
+#[tauri::command]
+pub async fn ssh_sftp_watch_stop(watch_id: String) -> Result<(), String> {
+    let mut map = WATCH_TASKS.lock().await;
+
+    if let Some(task) = map.remove(&watch_id) {
+        task.token.cancel();
+        Ok(())
+    } else {
+        Err("任务不存在".to_string())
+    }
+}
+
+/// 会话关闭时结束该会话的所有目录监听
+pub async fn close_session_watches(session_id: &str) {
+    let mut map = WATCH_TASKS.lock().await;
+    map.retain(|_, task| {
+        if task.session_id == session_id {
+            task.token.cancel();
+            false
+        } else {
+            true
+        }
+    });
+}
+
+/// (是否目录, 大小, 修改时间)
+type Snapshot = HashMap<String, (bool, u64, u64)>;
+
+async fn snapshot(session_id: &str, dir: &str) -> Result<Snapshot, String> {
+    let sftp = ssh_get_sftp(session_id).await?;
+    let entries = sftp.read_dir(dir).await.map_err(|e| e.to_string())?;
+    Ok(entries
+        .map(|entry| {
+            let metadata = entry.metadata();
+            (
+                entry.file_name(),
+                (
+                    metadata.file_type().is_dir(),
+                    metadata.size.unwrap_or(0),
+                    metadata.mtime.unwrap_or(0) as u64,
+                ),
+            )
+        })
+        .collect())
+}
+
+/// 定时列目录并与上一次结果比较
+async fn watch_poll(
+    session_id: &str,
+    dir: &str,
+    interval: Duration,
+    token: &CancellationToken,
+    on_watch_event: &Channel<DirWatchEvent>,
+) -> Result<(), String> {
+    let mut previous = snapshot(session_id, dir).await?;
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => return Ok(()),
+            _ = tokio::time::sleep(interval) => {}
+        }
+        let current = snapshot(session_id, dir).await?;
+
+        for (filename, &(is_dir, size, mtime)) in current.iter() {
+            let path = remote_join(dir, filename);
+            match previous.get(filename) {
+                None => {
+                    let _ = on_watch_event.send(DirWatchEvent::Added {
+                        path,
+                        filename: filename.clone(),
+                        is_dir,
+                        size,
+                        mtime,
+                    });
+                }
+                Some(&(_, old_size, old_mtime)) if old_size != size || old_mtime != mtime => {
+                    let _ = on_watch_event.send(DirWatchEvent::Modified {
+                        path,
+                        filename: filename.clone(),
+                        is_dir,
+                        size,
+                        mtime,
+                    });
+                }
+                _ => {}
+            }
+        }
+        for filename in previous.keys().filter(|name| !current.contains_key(*name)) {
+            let _ = on_watch_event.send(DirWatchEvent::Removed {
+                path: remote_join(dir, filename),
+                filename: filename.clone(),
+            });
+        }
+        previous = current;
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// `inotifywait -m` 每条事件输出 `事件/文件名/\n`，按文件合并后查询属性再上报
+/// 文件名不会包含 `/`，以 `/\n` 结尾分隔记录，文件名中的换行、制表符不影响解析
+async fn watch_inotify(
+    session_id: &str,
+    dir: &str,
+    token: &CancellationToken,
+    on_watch_event: &Channel<DirWatchEvent>,
+) -> Result<(), String> {
+    let command = format!(
+        "inotifywait -m -q -e create,delete,modify,attrib,moved_to,moved_from,delete_self,move_self --format '%e/%f/' -- {}",
+        shell_quote(dir)
+    );
+    let mut channel = exec_channel(session_id, &command).await?;
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut pending: HashMap<String, ChangeKind> = HashMap::new();
+    let mut ticker = tokio::time::interval(INOTIFY_COALESCE);
+
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => {
+                let _ = channel.close().await;
+                return Ok(());
+            }
+            _ = ticker.tick() => {
+                if !pending.is_empty() {
+                    flush_changes(session_id, dir, std::mem::take(&mut pending), on_watch_event).await;
+                }
+            }
+            msg = channel.wait() => match msg {
+                Some(russh::ChannelMsg::Data { ref data }) => {
+                    buffer.extend_from_slice(data);
+                    while let Some(pos) = buffer.windows(2).position(|w| w == b"/\n") {
+                        let record: Vec<u8> = buffer.drain(..pos + 2).collect();
+                        let record = String::from_utf8_lossy(&record[..pos]);
+                        let Some((events, filename)) = record.split_once('/') else {
+                            continue;
+                        };
+                        if events.contains("DELETE_SELF") || events.contains("MOVE_SELF") {
+                            let _ = channel.close().await;
+                            return Err(format!("Directory removed or moved: {}", dir));
+                        }
+                        if filename.is_empty() {
+                            continue;
+                        }
+                        let kind = if events.contains("CREATE") || events.contains("MOVED_TO") {
+                            ChangeKind::Added
+                        } else if events.contains("DELETE") || events.contains("MOVED_FROM") {
+                            ChangeKind::Removed
+                        } else {
+                            ChangeKind::Modified
+                        };
+                        // 新增后紧接着的修改仍视为新增；删除后重新创建视为新增；
+                        // 新增后又删除的文件前端从未见过，直接丢弃
+                        let merged = match (pending.get(filename), kind) {
+                            (Some(ChangeKind::Added), ChangeKind::Removed) => {
+                                pending.remove(filename);
+                                continue;
+                            }
+                            (Some(ChangeKind::Added), ChangeKind::Modified) => ChangeKind::Added,
+                            (Some(ChangeKind::Removed), ChangeKind::Modified) => ChangeKind::Removed,
+                            (_, kind) => kind,
+                        };
+                        pending.insert(filename.to_string(), merged);
+                    }
+                }
+                Some(russh::ChannelMsg::ExitStatus { exit_status }) => {
+                    return Err(format!("inotifywait exited with status {}", exit_status));
+                }
+                Some(russh::ChannelMsg::Close) | None => {
+                    return Err("inotifywait terminated".to_string());
+                }
+                _ => {}
+            },
+        }
+    }
+}
+
+async fn flush_changes(
+    session_id: &str,
+    dir: &str,
+    changes: HashMap<String, ChangeKind>,
+    on_watch_event: &Channel<DirWatchEvent>,
+) {
+    let sftp = ssh_get_sftp(session_id).await.ok();
+    for (filename, kind) in changes {
+        let path = remote_join(dir, &filename);
+        if kind == ChangeKind::Removed {
+            let _ = on_watch_event.send(DirWatchEvent::Removed { path, filename });
+            continue;
+        }
+        let metadata = match &sftp {
+            Some(sftp) => sftp.symlink_metadata(path.as_str()).await.ok(),
+            None => None,
+        };
+        let (is_dir, size, mtime) = metadata
+            .map(|m| (m.file_type().is_dir(), m.size.unwrap_or(0), m.mtime.unwrap_or(0) as u64))
+            .unwrap_or_default();
+        let event = if kind == ChangeKind::Added {
+            DirWatchEvent::Added { path, filename, is_dir, size, mtime }
+        } else {
+            DirWatchEvent::Modified { path, filename, is_dir, size, mtime }
+        };
+        let _ = on_watch_event.send(event);
+    }
+}